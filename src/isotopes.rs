@@ -0,0 +1,261 @@
+//! Theoretical isotopic distributions from an elemental composition or the averagine model.
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::peptide::PROTON;
+
+/// The mass difference between carbon 13 and carbon 12, used as the isotope spacing
+pub const NEUTRON_SHIFT: f64 = 1.0033548378;
+
+/// The average elemental composition of a residue of mass [`AVERAGINE_MASS`]
+pub const AVERAGINE: Composition = Composition {
+    carbon: 4.9384,
+    hydrogen: 7.7583,
+    nitrogen: 1.3577,
+    oxygen: 1.4773,
+    sulfur: 0.0417,
+};
+pub const AVERAGINE_MASS: f64 = 111.1254;
+
+/// The relative abundance of each element's isotopes, indexed by the number of extra neutrons
+const CARBON_ISOTOPES: &[f64] = &[0.9893, 0.0107];
+const HYDROGEN_ISOTOPES: &[f64] = &[0.999885, 0.000115];
+const NITROGEN_ISOTOPES: &[f64] = &[0.99636, 0.00364];
+const OXYGEN_ISOTOPES: &[f64] = &[0.99757, 0.00038, 0.00205];
+const SULFUR_ISOTOPES: &[f64] = &[0.9499, 0.0075, 0.0425, 0.0, 0.0001];
+
+const MONOISOTOPIC_MASSES: [f64; 5] = [
+    12.0,
+    1.00782503207,
+    14.0030740048,
+    15.99491461956,
+    31.97207100,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaError {
+    UnknownElement(String),
+    InvalidCount(String),
+}
+
+impl Display for FormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormulaError::UnknownElement(e) => write!(f, "Unsupported element {e:?}"),
+            FormulaError::InvalidCount(c) => write!(f, "Invalid element count {c:?}"),
+        }
+    }
+}
+
+impl Error for FormulaError {}
+
+/// An elemental composition of the elements found in peptides. Counts may be fractional
+/// when derived from the averagine model.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Composition {
+    pub carbon: f64,
+    pub hydrogen: f64,
+    pub nitrogen: f64,
+    pub oxygen: f64,
+    pub sulfur: f64,
+}
+
+impl Composition {
+    fn counts(&self) -> [f64; 5] {
+        [
+            self.carbon,
+            self.hydrogen,
+            self.nitrogen,
+            self.oxygen,
+            self.sulfur,
+        ]
+    }
+
+    pub fn monoisotopic_mass(&self) -> f64 {
+        self.counts()
+            .iter()
+            .zip(MONOISOTOPIC_MASSES)
+            .map(|(n, m)| n * m)
+            .sum()
+    }
+
+    /// Estimate the composition of a peptide of `neutral_mass` with the averagine model,
+    /// rounding to whole atoms and making up the remaining mass with hydrogen.
+    pub fn averagine(neutral_mass: f64) -> Self {
+        let units = neutral_mass / AVERAGINE_MASS;
+        let mut composition = Composition {
+            carbon: (AVERAGINE.carbon * units).round(),
+            hydrogen: 0.0,
+            nitrogen: (AVERAGINE.nitrogen * units).round(),
+            oxygen: (AVERAGINE.oxygen * units).round(),
+            sulfur: (AVERAGINE.sulfur * units).round(),
+        };
+        let remainder = neutral_mass - composition.monoisotopic_mass();
+        composition.hydrogen = (remainder / MONOISOTOPIC_MASSES[1]).round().max(0.0);
+        composition
+    }
+
+    /// The relative abundances of the first `max_peaks` isotopic peaks, summing to 1
+    pub fn isotopic_distribution(&self, max_peaks: usize) -> Vec<f64> {
+        let max_peaks = max_peaks.max(1);
+        let isotopes = [
+            CARBON_ISOTOPES,
+            HYDROGEN_ISOTOPES,
+            NITROGEN_ISOTOPES,
+            OXYGEN_ISOTOPES,
+            SULFUR_ISOTOPES,
+        ];
+        let mut distribution = vec![1.0];
+        for (count, isotopes) in self.counts().into_iter().zip(isotopes) {
+            let element = polynomial_power(isotopes, count.round().max(0.0) as usize, max_peaks);
+            distribution = convolve(&distribution, &element, max_peaks);
+        }
+        let total: f64 = distribution.iter().sum();
+        distribution.iter_mut().for_each(|v| *v /= total);
+        distribution
+    }
+}
+
+impl FromStr for Composition {
+    type Err = FormulaError;
+
+    /// Parse a chemical formula like `C6H12O6`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut composition = Composition::default();
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            let symbol_end = rest
+                .char_indices()
+                .skip(1)
+                .find(|(_, c)| !c.is_ascii_lowercase())
+                .map(|(i, _)| i)
+                .unwrap_or(rest.len());
+            let (symbol, tail) = rest.split_at(symbol_end);
+            let count_end = tail
+                .char_indices()
+                .find(|(_, c)| !c.is_ascii_digit())
+                .map(|(i, _)| i)
+                .unwrap_or(tail.len());
+            let (count, tail) = tail.split_at(count_end);
+            let count: f64 = if count.is_empty() {
+                1.0
+            } else {
+                count
+                    .parse()
+                    .map_err(|_| FormulaError::InvalidCount(count.to_string()))?
+            };
+            match symbol {
+                "C" => composition.carbon += count,
+                "H" => composition.hydrogen += count,
+                "N" => composition.nitrogen += count,
+                "O" => composition.oxygen += count,
+                "S" => composition.sulfur += count,
+                _ => return Err(FormulaError::UnknownElement(symbol.to_string())),
+            }
+            rest = tail;
+        }
+        Ok(composition)
+    }
+}
+
+fn convolve(a: &[f64], b: &[f64], max_len: usize) -> Vec<f64> {
+    let mut result = vec![0.0; (a.len() + b.len() - 1).min(max_len)];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            if let Some(slot) = result.get_mut(i + j) {
+                *slot += x * y;
+            }
+        }
+    }
+    result
+}
+
+/// Raise the polynomial `base` to the `exponent` power by repeated squaring, keeping only
+/// the first `max_len` terms.
+fn polynomial_power(base: &[f64], mut exponent: usize, max_len: usize) -> Vec<f64> {
+    let mut result = vec![1.0];
+    let mut base = base.to_vec();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = convolve(&result, &base, max_len);
+        }
+        base = convolve(&base, &base, max_len);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// How to derive the elemental composition of an analyte
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum IsotopeModel {
+    /// Estimate the composition from the analyte's mass
+    #[default]
+    Averagine,
+    /// Use a known composition for every analyte
+    Formula(Composition),
+}
+
+impl IsotopeModel {
+    pub fn composition(&self, neutral_mass: f64) -> Composition {
+        match self {
+            IsotopeModel::Averagine => Composition::averagine(neutral_mass),
+            IsotopeModel::Formula(composition) => *composition,
+        }
+    }
+
+    /// The m/z and relative abundance (summing to 1) of each isotopic peak of an analyte of
+    /// `neutral_mass` at `charge`, dropping peaks less abundant than `min_abundance`.
+    pub fn isotopic_pattern(
+        &self,
+        neutral_mass: f64,
+        charge: i32,
+        max_peaks: usize,
+        min_abundance: f64,
+    ) -> Vec<(f64, f64)> {
+        let charge = if charge == 0 { 1 } else { charge };
+        let z = charge.abs() as f64;
+        let proton = PROTON * charge.signum() as f64;
+        self.composition(neutral_mass)
+            .isotopic_distribution(max_peaks)
+            .into_iter()
+            .enumerate()
+            .map(|(i, abundance)| {
+                (
+                    (neutral_mass + i as f64 * NEUTRON_SHIFT + proton * z) / z,
+                    abundance,
+                )
+            })
+            .filter(|(_, abundance)| *abundance >= min_abundance)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_isotopic_distribution() {
+        let glucose: Composition = "C6H12O6".parse().unwrap();
+        assert!((glucose.monoisotopic_mass() - 180.063388).abs() < 1e-4);
+        let dist = glucose.isotopic_distribution(4);
+        assert_eq!(dist.len(), 4);
+        assert!(dist[0] > 0.9 && dist[1] > 0.05 && dist[1] < 0.08);
+        assert!((dist.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        assert_eq!(
+            "C6Xx2".parse::<Composition>(),
+            Err(FormulaError::UnknownElement("Xx".to_string()))
+        );
+
+        // A ~2.5 kDa peptide has an M+1 peak more abundant than its monoisotopic peak
+        let composition = Composition::averagine(2500.0);
+        assert!((composition.monoisotopic_mass() - 2500.0).abs() < 1.0);
+        let dist = composition.isotopic_distribution(6);
+        assert!(dist[1] > dist[0]);
+
+        let pattern = IsotopeModel::Averagine.isotopic_pattern(1000.0, 2, 10, 0.01);
+        assert!((pattern[0].0 - 501.007276).abs() < 1e-5);
+        assert!((pattern[1].0 - pattern[0].0 - NEUTRON_SHIFT / 2.0).abs() < 1e-9);
+        assert!(pattern.len() < 10);
+    }
+}
@@ -1,11 +1,15 @@
 
 mod linear;
 
+pub mod isotopes;
+pub mod mzpaf;
+pub mod peptide;
 pub mod util;
 pub mod v2;
+pub mod xic;
 
 pub use v2::*;
-pub use linear::{CoordinateRange, Scale};
+pub use linear::{CoordinateRange, Scale, ScaleSegments, ScaleType, SegmentedScale};
 
 /// Re-exported from [`svg`] for convenience
 pub use svg::{Document, node::{element::{Group, self}, Node, self, Value}};
\ No newline at end of file
@@ -1,3 +1,6 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
 use num_traits::Float;
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -12,7 +15,8 @@ impl<T: Float> CoordinateRange<T> {
     }
 
     pub fn is_well_formed(&self) -> bool {
-        (self.start.is_finite() && !self.start.is_nan()) && (self.end.is_finite() && !self.end.is_nan())
+        (self.start.is_finite() && !self.start.is_nan())
+            && (self.end.is_finite() && !self.end.is_nan())
     }
 
     pub fn size(&self) -> T {
@@ -29,13 +33,9 @@ impl<T: Float> CoordinateRange<T> {
     }
 
     pub fn to_f64(&self) -> CoordinateRange<f64> {
-        CoordinateRange::new(
-            self.start.to_f64().unwrap(),
-            self.end.to_f64().unwrap()
-        )
+        CoordinateRange::new(self.start.to_f64().unwrap(), self.end.to_f64().unwrap())
     }
 
-    #[allow(unused)]
     pub fn min(&self) -> T {
         self.start.min(self.end)
     }
@@ -44,6 +44,11 @@ impl<T: Float> CoordinateRange<T> {
         self.start.max(self.end)
     }
 
+    /// Test whether `value` lies between the ends of the range, inclusive, in either orientation
+    pub fn contains(&self, value: T) -> bool {
+        self.min() <= value && value <= self.max()
+    }
+
     pub fn clamp(&self, value: T) -> T {
         let (min, max) = if self.start < self.end {
             (self.start, self.end)
@@ -79,10 +84,65 @@ impl<T: Float> From<core::ops::RangeTo<T>> for CoordinateRange<T> {
     }
 }
 
+/// How values are mapped from a [`Scale`]'s domain before being
+/// interpolated onto its range.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ScaleType {
+    #[default]
+    Linear,
+    /// A base 10 logarithm. Values at or below `floor` are clamped to `floor`,
+    /// which keeps zero-intensity baselines drawable.
+    Log10 { floor: f64 },
+    /// A square root, preserving the sign of the value
+    Sqrt,
+    /// A symmetric logarithm which is approximately linear within `constant` of zero
+    SymLog { constant: f64 },
+}
+
+impl ScaleType {
+    /// A [`ScaleType::Log10`] which clamps values below 1.0
+    pub fn log10() -> Self {
+        Self::Log10 { floor: 1.0 }
+    }
+
+    /// A [`ScaleType::SymLog`] with a linear region of width 1.0
+    pub fn symlog() -> Self {
+        Self::SymLog { constant: 1.0 }
+    }
+
+    pub fn is_linear(&self) -> bool {
+        matches!(self, Self::Linear)
+    }
+
+    /// Map `value` from data space into the space the scale interpolates in
+    pub fn forward(&self, value: f64) -> f64 {
+        match self {
+            ScaleType::Linear => value,
+            ScaleType::Log10 { floor } => value.max(*floor).log10(),
+            ScaleType::Sqrt => value.signum() * value.abs().sqrt(),
+            ScaleType::SymLog { constant } => value.signum() * (value.abs() / constant).ln_1p(),
+        }
+    }
+
+    /// The inverse of [`ScaleType::forward`]
+    pub fn inverse(&self, value: f64) -> f64 {
+        match self {
+            ScaleType::Linear => value,
+            ScaleType::Log10 { .. } => 10.0f64.powf(value),
+            ScaleType::Sqrt => value.signum() * value.powi(2),
+            ScaleType::SymLog { constant } => value.signum() * value.abs().exp_m1() * constant,
+        }
+    }
+}
+
+/// The default number of range units left between the segments of a broken [`Scale`]
+pub const DEFAULT_SEGMENT_GAP: f64 = 12.0;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Scale<T: Float> {
     pub domain: CoordinateRange<T>,
     pub range: CoordinateRange<T>,
+    pub scale_type: ScaleType,
 }
 
 #[allow(unused)]
@@ -92,20 +152,538 @@ impl<T: Float> Scale<T> {
     }
 
     pub fn new(domain: CoordinateRange<T>, range: CoordinateRange<T>) -> Self {
-        Self { domain, range }
+        Self {
+            domain,
+            range,
+            scale_type: ScaleType::Linear,
+        }
+    }
+
+    pub fn with_scale_type(mut self, scale_type: ScaleType) -> Self {
+        self.scale_type = scale_type;
+        self
+    }
+
+    /// The domain's bounds after applying [`ScaleType::forward`]
+    fn transformed_domain(&self) -> (f64, f64) {
+        (
+            self.scale_type.forward(self.domain.start.to_f64().unwrap()),
+            self.scale_type.forward(self.domain.end.to_f64().unwrap()),
+        )
+    }
+
+    /// Compute the fractional position of `value` within the domain
+    fn normalize(&self, value: T) -> f64 {
+        if self.scale_type.is_linear() {
+            self.domain.transform(value)
+        } else {
+            let (start, end) = self.transformed_domain();
+            (self.scale_type.forward(value.to_f64().unwrap()) - start) / (end - start)
+        }
+    }
+
+    pub fn transform(&self, value: T) -> T {
+        let i = self.normalize(value);
+        self.range.inverse_transform(i)
+    }
+
+    pub fn inverse_transform(&self, value: T) -> T {
+        let i = self.range.transform(value);
+        if self.scale_type.is_linear() {
+            self.domain.inverse_transform(i)
+        } else {
+            let (start, end) = self.transformed_domain();
+            let v = self.scale_type.inverse(start + (end - start) * i);
+            T::from(v).unwrap()
+        }
+    }
+
+    /// Generate approximately `count` tick locations spanning the domain, in ascending order.
+    ///
+    /// Linear and square root scales place ticks on "nice" multiples of 1, 2 or 5 x 10<sup>n</sup>,
+    /// see [`nice_ticks`]. Logarithmic scales place ticks on powers of ten when there are enough
+    /// of them in the domain, otherwise ticks are evenly spaced in the transformed space.
+    pub fn ticks(&self, count: usize) -> Vec<T> {
+        let count = count.max(1);
+        let lo = self.domain.min().to_f64().unwrap();
+        let hi = self.domain.max().to_f64().unwrap();
+
+        let ticks = match self.scale_type {
+            ScaleType::Linear | ScaleType::Sqrt => nice_ticks(lo, hi, count),
+            ScaleType::Log10 { floor } => {
+                let lo = lo.max(floor);
+                let hi = hi.max(floor);
+                let ticks: Vec<f64> = (lo.log10().ceil() as i32..=hi.log10().floor() as i32)
+                    .map(|e| 10.0f64.powi(e))
+                    .collect();
+                if ticks.len() >= 2 {
+                    ticks
+                } else {
+                    self.transformed_even_ticks(lo, hi, count)
+                }
+            }
+            ScaleType::SymLog { constant } => {
+                let mut ticks = Vec::new();
+                if lo <= 0.0 && hi >= 0.0 {
+                    ticks.push(0.0);
+                }
+                let mut e = constant.log10().ceil() as i32;
+                while 10.0f64.powi(e) <= lo.abs().max(hi.abs()) {
+                    let v = 10.0f64.powi(e);
+                    if v >= lo && v <= hi {
+                        ticks.push(v);
+                    }
+                    if -v >= lo && -v <= hi {
+                        ticks.push(-v);
+                    }
+                    e += 1;
+                }
+                if ticks.len() >= 2 {
+                    ticks.sort_by(|a, b| a.total_cmp(b));
+                    ticks
+                } else {
+                    self.transformed_even_ticks(lo, hi, count)
+                }
+            }
+        };
+        ticks.into_iter().map(|v| T::from(v).unwrap()).collect()
+    }
+
+    /// Generate minor tick locations between and around the `major` tick locations,
+    /// excluding the major ticks themselves.
+    ///
+    /// Each interval between major ticks is split into `subdivisions` pieces. Logarithmic
+    /// scales whose major ticks are powers of ten instead place minor ticks on 2-9 x 10<sup>n</sup>.
+    pub fn minor_ticks(&self, major: &[T], subdivisions: usize) -> Vec<T> {
+        if subdivisions < 2 || major.len() < 2 {
+            return Vec::new();
+        }
+        let lo = self.domain.min().to_f64().unwrap();
+        let hi = self.domain.max().to_f64().unwrap();
+        let major: Vec<f64> = major.iter().map(|v| v.to_f64().unwrap()).collect();
+        let is_major = |v: f64| {
+            major
+                .iter()
+                .any(|m| (m - v).abs() <= (hi - lo).abs() * 1e-9)
+        };
+
+        let mut ticks = Vec::new();
+        match self.scale_type {
+            ScaleType::Log10 { floor } => {
+                let lo = lo.max(floor);
+                for e in (lo.log10().floor() as i32)..=(hi.max(floor).log10().floor() as i32) {
+                    let base = 10.0f64.powi(e);
+                    for k in 2..10 {
+                        let v = base * k as f64;
+                        if v >= lo && v <= hi {
+                            ticks.push(v);
+                        }
+                    }
+                }
+            }
+            _ => {
+                // Extend one interval past each end so the minor ticks cover the
+                // whole domain, not just the span between the first and last major tick.
+                let first_step = major[1] - major[0];
+                let last_step = major[major.len() - 1] - major[major.len() - 2];
+                let mut bounds = vec![major[0] - first_step];
+                bounds.extend(major.iter().copied());
+                bounds.push(major[major.len() - 1] + last_step);
+                for pair in bounds.windows(2) {
+                    let step = (pair[1] - pair[0]) / subdivisions as f64;
+                    for i in 1..subdivisions {
+                        let v = pair[0] + step * i as f64;
+                        if v >= lo && v <= hi && !is_major(v) {
+                            ticks.push(v);
+                        }
+                    }
+                }
+            }
+        }
+        ticks
+            .into_iter()
+            .filter(|v| !is_major(*v))
+            .map(|v| T::from(v).unwrap())
+            .collect()
+    }
+
+    fn transformed_even_ticks(&self, lo: f64, hi: f64, count: usize) -> Vec<f64> {
+        even_ticks(
+            self.scale_type.forward(lo),
+            self.scale_type.forward(hi),
+            count,
+        )
+        .into_iter()
+        .map(|v| self.scale_type.inverse(v))
+        .collect()
+    }
+}
+
+/// The disjoint sub-ranges of a [`Scale`]'s domain which a broken axis displays, ascending
+/// and in order. These are kept apart from the [`Scale`] so that it stays [`Copy`], and are
+/// combined with one by [`ScaleSegments::apply`].
+///
+/// When empty, the entire domain is displayed.
+#[derive(Debug, Clone)]
+pub struct ScaleSegments<T: Float> {
+    segments: Arc<[CoordinateRange<T>]>,
+    /// The number of range units left empty between consecutive segments
+    pub gap: f64,
+}
+
+impl<T: Float> Default for ScaleSegments<T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T: Float> Deref for ScaleSegments<T> {
+    type Target = [CoordinateRange<T>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.segments
+    }
+}
+
+impl<T: Float> ScaleSegments<T> {
+    /// Re-orient `segments` to be ascending and sort them. Segments with a bound which
+    /// is not finite are discarded.
+    pub fn new(segments: Vec<CoordinateRange<T>>) -> Self {
+        let mut segments: Vec<_> = segments
+            .into_iter()
+            .filter(|s| s.is_well_formed())
+            .map(|s| CoordinateRange::new(s.min(), s.max()))
+            .collect();
+        segments.sort_by(|a, b| {
+            a.start
+                .to_f64()
+                .unwrap()
+                .total_cmp(&b.start.to_f64().unwrap())
+        });
+        Self {
+            segments: segments.into(),
+            gap: DEFAULT_SEGMENT_GAP,
+        }
+    }
+
+    pub fn with_gap(mut self, gap: f64) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Break `scale` into these segments
+    pub fn apply(&self, scale: Scale<T>) -> SegmentedScale<'_, T> {
+        SegmentedScale {
+            scale,
+            segments: &self.segments,
+            segment_gap: self.gap,
+        }
+    }
+}
+
+/// A [`Scale`] broken into disjoint `segments` of its domain, making it a broken axis scale.
+/// Each segment receives a share of the range proportional to its (transformed) width.
+///
+/// Without segments, this behaves exactly like the underlying [`Scale`].
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentedScale<'a, T: Float> {
+    pub scale: Scale<T>,
+    pub segments: &'a [CoordinateRange<T>],
+    /// The number of range units left empty between consecutive segments
+    pub segment_gap: f64,
+}
+
+impl<T: Float> From<Scale<T>> for SegmentedScale<'_, T> {
+    fn from(scale: Scale<T>) -> Self {
+        Self {
+            scale,
+            segments: &[],
+            segment_gap: DEFAULT_SEGMENT_GAP,
+        }
+    }
+}
+
+impl<'a, T: Float> From<&'a Scale<T>> for SegmentedScale<'a, T> {
+    fn from(scale: &'a Scale<T>) -> Self {
+        Self::from(*scale)
+    }
+}
+
+impl<T: Float> Deref for SegmentedScale<'_, T> {
+    type Target = Scale<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.scale
+    }
+}
+
+/// The placement of one segment of a [`SegmentedScale`], with the segment's bounds
+/// in transformed domain space and its normalized position along the range
+#[derive(Debug, Clone, Copy)]
+struct SegmentPlacement {
+    domain_start: f64,
+    domain_end: f64,
+    position_start: f64,
+    position_end: f64,
+}
+
+impl SegmentPlacement {
+    fn position_of(&self, value: f64) -> f64 {
+        let frac = (value - self.domain_start) / (self.domain_end - self.domain_start);
+        self.position_start + frac * (self.position_end - self.position_start)
+    }
+
+    fn domain_at(&self, position: f64) -> f64 {
+        let frac = (position - self.position_start) / (self.position_end - self.position_start);
+        self.domain_start + frac * (self.domain_end - self.domain_start)
+    }
+}
+
+impl<T: Float> SegmentedScale<'_, T> {
+    pub fn is_segmented(&self) -> bool {
+        !self.segments.is_empty()
+    }
+
+    /// Find the index of the segment containing `value`.
+    ///
+    /// A scale without segments treats every value as belonging to segment 0, while
+    /// values which fall between or outside the segments of a broken scale have no segment.
+    pub fn segment_of(&self, value: T) -> Option<usize> {
+        if self.segments.is_empty() {
+            Some(0)
+        } else {
+            self.segments.iter().position(|s| s.contains(value))
+        }
+    }
+
+    /// The `(start, end)` range coordinates each segment occupies, in segment order.
+    /// A scale without segments has a single segment spanning the whole range.
+    pub fn segment_ranges(&self) -> Vec<(T, T)> {
+        if self.segments.is_empty() {
+            return vec![(self.range.min(), self.range.max())];
+        }
+        let inverted = self.domain.start > self.domain.end;
+        self.segment_placements()
+            .map(|p| {
+                let (a, b) = if inverted {
+                    (1.0 - p.position_end, 1.0 - p.position_start)
+                } else {
+                    (p.position_start, p.position_end)
+                };
+                let a = self.range.inverse_transform(a);
+                let b = self.range.inverse_transform(b);
+                (a.min(b), a.max(b))
+            })
+            .collect()
+    }
+
+    /// Lay the segments out along the range. This is done lazily without allocating, as it
+    /// is needed for every transformed value.
+    fn segment_placements(&self) -> impl Iterator<Item = SegmentPlacement> + '_ {
+        let n = self.segments.len();
+        let length = self.range.size().abs().to_f64().unwrap().max(f64::EPSILON);
+        let gap = (self.segment_gap / length).min(0.5 / n as f64);
+        let usable = 1.0 - gap * (n.saturating_sub(1)) as f64;
+
+        let scale_type = self.scale_type;
+        let bounds = self.segments.iter().map(move |s| {
+            (
+                scale_type.forward(s.start.to_f64().unwrap()),
+                scale_type.forward(s.end.to_f64().unwrap()),
+            )
+        });
+        let total: f64 = bounds.clone().map(|(a, b)| b - a).sum();
+
+        bounds.scan(0.0, move |position, (domain_start, domain_end)| {
+            let share = if total > 0.0 {
+                (domain_end - domain_start) / total * usable
+            } else {
+                usable / n as f64
+            };
+            let placement = SegmentPlacement {
+                domain_start,
+                domain_end,
+                position_start: *position,
+                position_end: *position + share,
+            };
+            *position += share + gap;
+            Some(placement)
+        })
+    }
+
+    /// Compute the fractional position of `value` within the range.
+    ///
+    /// Values which fall between two segments are placed at the end of the lower segment,
+    /// while values beyond the first or last segment are extrapolated from it.
+    fn normalize(&self, value: T) -> f64 {
+        let v = self.scale_type.forward(value.to_f64().unwrap());
+        let (i, placement) = self
+            .segment_placements()
+            .enumerate()
+            .take_while(|(i, p)| *i == 0 || p.domain_start <= v)
+            .last()
+            .unwrap();
+        let position = if i + 1 == self.segments.len() || v < placement.domain_start {
+            placement.position_of(v)
+        } else {
+            placement.position_of(v.min(placement.domain_end))
+        };
+        if self.domain.start > self.domain.end {
+            1.0 - position
+        } else {
+            position
+        }
     }
 
     pub fn transform(&self, value: T) -> T {
-        let i = self.domain.transform(value);
+        if !self.is_segmented() {
+            return self.scale.transform(value);
+        }
+        let i = self.normalize(value);
         self.range.inverse_transform(i)
     }
 
     pub fn inverse_transform(&self, value: T) -> T {
+        if !self.is_segmented() {
+            return self.scale.inverse_transform(value);
+        }
         let i = self.range.transform(value);
-        self.domain.inverse_transform(i)
+        let i = if self.domain.start > self.domain.end {
+            1.0 - i
+        } else {
+            i
+        };
+        let (k, placement) = self
+            .segment_placements()
+            .enumerate()
+            .take_while(|(k, p)| *k == 0 || p.position_start <= i)
+            .last()
+            .unwrap();
+        let v = if k + 1 == self.segments.len() || i < placement.position_start {
+            placement.domain_at(i)
+        } else {
+            placement.domain_at(i.min(placement.position_end))
+        };
+        T::from(self.scale_type.inverse(v)).unwrap()
+    }
+
+    /// Create an unbroken scale covering only `segment`
+    fn segment_scale(&self, segment: CoordinateRange<T>) -> Scale<T> {
+        Scale::new(segment, self.range).with_scale_type(self.scale_type)
+    }
+
+    /// Generate tick locations like [`Scale::ticks`], distributing the requested ticks across
+    /// segments by their share of the range.
+    pub fn ticks(&self, count: usize) -> Vec<T> {
+        if !self.is_segmented() {
+            return self.scale.ticks(count);
+        }
+        let count = count.max(1);
+        self.segments
+            .iter()
+            .zip(self.segment_placements())
+            .flat_map(|(segment, placement)| {
+                let share = placement.position_end - placement.position_start;
+                let n = ((count as f64) * share).round().max(1.0) as usize;
+                self.segment_scale(*segment).ticks(n)
+            })
+            .collect()
+    }
+
+    /// Generate minor tick locations like [`Scale::minor_ticks`] within each segment
+    pub fn minor_ticks(&self, major: &[T], subdivisions: usize) -> Vec<T> {
+        if !self.is_segmented() {
+            return self.scale.minor_ticks(major, subdivisions);
+        }
+        self.segments
+            .iter()
+            .flat_map(|segment| {
+                let major: Vec<T> = major
+                    .iter()
+                    .copied()
+                    .filter(|v| segment.contains(*v))
+                    .collect();
+                self.segment_scale(*segment)
+                    .minor_ticks(&major, subdivisions)
+            })
+            .collect()
     }
 }
 
+/// Compute a step size of 1, 2 or 5 x 10<sup>n</sup> that divides `span` into approximately `count` pieces
+pub fn nice_step(span: f64, count: usize) -> f64 {
+    let raw_step = span.abs() / count.max(1) as f64;
+    let power = raw_step.log10().floor();
+    let error = raw_step / 10.0f64.powf(power);
+    let factor = if error >= 50.0f64.sqrt() {
+        10.0
+    } else if error >= 10.0f64.sqrt() {
+        5.0
+    } else if error >= 2.0f64.sqrt() {
+        2.0
+    } else {
+        1.0
+    };
+    factor * 10.0f64.powf(power)
+}
+
+/// Generate approximately `count` ascending tick locations within `lo..=hi` that fall
+/// on multiples of [`nice_step`], in the style of d3's `ticks` or matplotlib's `MaxNLocator`.
+///
+/// If the interval is empty or not finite, only `lo` is returned.
+pub fn nice_ticks(lo: f64, hi: f64, count: usize) -> Vec<f64> {
+    let (lo, hi) = if lo <= hi { (lo, hi) } else { (hi, lo) };
+    if !(lo.is_finite() && hi.is_finite()) || hi - lo <= 0.0 {
+        return vec![lo];
+    }
+    let step = nice_step(hi - lo, count);
+    if step >= 1.0 {
+        let start = (lo / step).ceil() as i64;
+        let end = (hi / step).floor() as i64;
+        (start..=end).map(|i| i as f64 * step).collect()
+    } else {
+        // Divide by the inverse of fractional steps to avoid accumulating rounding error,
+        // e.g. 3 * 0.1 != 0.3 but 3 / 10 == 0.3
+        let inverse = (1.0 / step).round();
+        let start = (lo * inverse).ceil() as i64;
+        let end = (hi * inverse).floor() as i64;
+        (start..=end).map(|i| i as f64 / inverse).collect()
+    }
+}
+
+/// Find the regions of `positions` which are separated by more than `min_gap`, padding
+/// each region by `padding` on either side.
+///
+/// This is used to build the segments of a broken axis which skips empty regions.
+pub fn segments_from_positions(
+    positions: impl IntoIterator<Item = f64>,
+    min_gap: f64,
+    padding: f64,
+) -> Vec<CoordinateRange<f64>> {
+    let mut positions: Vec<f64> = positions.into_iter().filter(|v| v.is_finite()).collect();
+    positions.sort_by(|a, b| a.total_cmp(b));
+
+    let mut segments: Vec<CoordinateRange<f64>> = Vec::new();
+    for v in positions {
+        match segments.last_mut() {
+            Some(last) if v - last.end <= min_gap => {
+                last.end = v;
+            }
+            _ => segments.push(CoordinateRange::new(v, v)),
+        }
+    }
+    for segment in segments.iter_mut() {
+        segment.start -= padding;
+        segment.end += padding;
+    }
+    segments
+}
+
+/// Split `lo..=hi` into `count` equal steps
+fn even_ticks(lo: f64, hi: f64, count: usize) -> Vec<f64> {
+    let step = (hi - lo) / count as f64;
+    (0..=count).map(|i| lo + step * i as f64).collect()
+}
 
 #[allow(unused)]
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
@@ -131,7 +709,6 @@ impl SVGTransform {
     }
 }
 
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -169,4 +746,128 @@ mod test {
         let p = c.transform(20.0);
         assert_eq!(p, 0.8);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_log_scale() {
+        let scale = Scale::new(
+            CoordinateRange::new(1.0, 1000.0),
+            CoordinateRange::new(0.0, 300.0),
+        )
+        .with_scale_type(ScaleType::log10());
+
+        assert!((scale.transform(10.0) - 100.0).abs() < 1e-9);
+        assert!((scale.inverse_transform(200.0) - 100.0).abs() < 1e-9);
+        // Clamped to the floor rather than producing -inf
+        assert_eq!(scale.transform(0.0), 0.0);
+        assert_eq!(scale.ticks(5), vec![1.0, 10.0, 100.0, 1000.0]);
+    }
+
+    #[test]
+    fn test_sqrt_symlog_scale() {
+        let scale = Scale::new(
+            CoordinateRange::new(100.0, 0.0),
+            CoordinateRange::new(0.0, 10.0),
+        )
+        .with_scale_type(ScaleType::Sqrt);
+        assert!((scale.transform(25.0) - 5.0).abs() < 1e-9);
+        assert!((scale.inverse_transform(5.0) - 25.0).abs() < 1e-9);
+
+        let scale = Scale::new(
+            CoordinateRange::new(-1000.0, 1000.0),
+            CoordinateRange::new(0.0, 100.0),
+        )
+        .with_scale_type(ScaleType::symlog());
+        assert!((scale.transform(0.0) - 50.0).abs() < 1e-9);
+        assert!((scale.inverse_transform(scale.transform(42.0)) - 42.0).abs() < 1e-9);
+        let ticks = scale.ticks(5);
+        assert!(ticks.contains(&0.0));
+        assert!(ticks.contains(&-100.0));
+    }
+
+    #[test]
+    fn test_nice_ticks() {
+        assert_eq!(nice_step(1573.57, 5), 200.0);
+        let ticks = nice_ticks(437.83, 2011.4, 5);
+        assert_eq!(
+            ticks,
+            vec![600.0, 800.0, 1000.0, 1200.0, 1400.0, 1600.0, 1800.0, 2000.0]
+        );
+
+        let ticks = nice_ticks(0.0, 1.0, 5);
+        assert_eq!(ticks, vec![0.0, 0.2, 0.4, 0.6, 0.8, 1.0]);
+
+        let ticks = nice_ticks(0.3, 0.0, 3);
+        assert_eq!(ticks, vec![0.0, 0.1, 0.2, 0.3]);
+
+        // An inverted intensity axis yields the same ascending ticks
+        let scale = Scale::new(
+            CoordinateRange::new(12345.0, 0.0),
+            CoordinateRange::new(0.0, 200.0),
+        );
+        assert_eq!(scale.ticks(2), vec![0.0, 5000.0, 10000.0]);
+    }
+
+    #[test]
+    fn test_minor_ticks() {
+        let scale = Scale::new(
+            CoordinateRange::new(0.0, 250.0),
+            CoordinateRange::new(0.0, 100.0),
+        );
+        let minor = scale.minor_ticks(&[0.0, 100.0, 200.0], 4);
+        assert_eq!(
+            minor,
+            vec![25.0, 50.0, 75.0, 125.0, 150.0, 175.0, 225.0, 250.0]
+        );
+
+        let scale = scale.with_scale_type(ScaleType::log10());
+        let minor = scale.minor_ticks(&[1.0, 10.0, 100.0], 10);
+        assert_eq!(minor.len(), 8 + 8 + 1);
+        assert_eq!(minor[0], 2.0);
+        assert_eq!(*minor.last().unwrap(), 200.0);
+    }
+
+    #[test]
+    fn test_segmented_scale() {
+        let scale = Scale::new(
+            CoordinateRange::new(100.0, 1000.0),
+            CoordinateRange::new(0.0, 412.0),
+        );
+        let segments = ScaleSegments::new(vec![
+            CoordinateRange::new(800.0, 1000.0),
+            CoordinateRange::new(f64::NAN, 50.0),
+            CoordinateRange::new(100.0, 300.0),
+        ]);
+        let scale = segments.apply(scale);
+        // Two segments of equal width share 400 pixels around a 12 pixel gap
+        assert_eq!(segments.len(), 2);
+        assert_eq!(scale.segments[0].start, 100.0);
+        assert!((scale.transform(100.0) - 0.0).abs() < 1e-9);
+        assert!((scale.transform(300.0) - 200.0).abs() < 1e-9);
+        assert!((scale.transform(800.0) - 212.0).abs() < 1e-9);
+        assert!((scale.transform(900.0) - 312.0).abs() < 1e-9);
+        // Values in the gap are pinned to the end of the lower segment
+        assert!((scale.transform(500.0) - 200.0).abs() < 1e-9);
+        assert!((scale.inverse_transform(312.0) - 900.0).abs() < 1e-9);
+
+        assert_eq!(scale.segment_of(250.0), Some(0));
+        assert_eq!(scale.segment_of(500.0), None);
+        assert_eq!(scale.segment_of(850.0), Some(1));
+
+        let ranges = scale.segment_ranges();
+        assert!((ranges[1].0 - 212.0).abs() < 1e-9);
+
+        let ticks = scale.ticks(4);
+        assert!(ticks.iter().all(|t| scale.segment_of(*t).is_some()));
+    }
+
+    #[test]
+    fn test_segments_from_positions() {
+        let segments =
+            segments_from_positions(vec![510.0, 500.0, 520.0, 1500.0, 1510.0], 100.0, 5.0);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].start, 495.0);
+        assert_eq!(segments[0].end, 525.0);
+        assert_eq!(segments[1].start, 1495.0);
+        assert_eq!(segments[1].end, 1515.0);
+    }
+}
@@ -0,0 +1,464 @@
+//! A reader for the HUPO-PSI [peak annotation format](https://www.psidev.info/mzPAF) (mzPAF),
+//! e.g. `b5^2/1.2ppm`, `y7-H2O` or `p^3`.
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use crate::peptide::{charge_superscript, IonType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MzPafError {
+    Empty,
+    UnexpectedCharacter(char, usize),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    UnclosedBracket(usize),
+}
+
+impl Display for MzPafError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MzPafError::Empty => write!(f, "The annotation is empty"),
+            MzPafError::UnexpectedCharacter(c, i) => {
+                write!(f, "Unexpected character {c:?} at position {i}")
+            }
+            MzPafError::UnexpectedEnd => write!(f, "The annotation ended unexpectedly"),
+            MzPafError::InvalidNumber(s) => write!(f, "Invalid number {s:?}"),
+            MzPafError::UnclosedBracket(i) => {
+                write!(f, "Unclosed bracket starting at position {i}")
+            }
+        }
+    }
+}
+
+impl Error for MzPafError {}
+
+/// What kind of ion produced the peak
+#[derive(Debug, Clone, PartialEq)]
+pub enum PafIon {
+    /// A backbone fragment like `b5` or `y7`
+    Series { ion_type: IonType, ordinal: usize },
+    /// An internal fragment spanning residues `start` through `end`, like `m3:6`
+    Internal { start: usize, end: usize },
+    /// An immonium ion of the named residue, like `IY`
+    Immonium(String),
+    /// The precursor ion, `p`
+    Precursor,
+    /// A named reporter ion, like `r[TMT127N]`
+    Reporter(String),
+    /// An ion described by its chemical formula, like `f{C13H9}`
+    Formula(String),
+    /// An ion described by a free text name, like `_{Urocanic Acid}`
+    Named(String),
+    /// An unexplained peak, optionally numbered, like `?` or `?17`
+    Unknown(Option<usize>),
+}
+
+impl PafIon {
+    /// The color used to draw peaks explained by this kind of ion
+    pub fn color(&self) -> &'static str {
+        match self {
+            PafIon::Series { ion_type, .. } => ion_type.color(),
+            PafIon::Internal { .. } => "olive",
+            PafIon::Immonium(_) => "darkcyan",
+            PafIon::Precursor => "dimgray",
+            PafIon::Reporter(_) => "darkmagenta",
+            PafIon::Formula(_) | PafIon::Named(_) => "black",
+            PafIon::Unknown(_) => "gray",
+        }
+    }
+}
+
+impl Display for PafIon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PafIon::Series { ion_type, ordinal } => write!(f, "{}{ordinal}", ion_type.symbol()),
+            PafIon::Internal { start, end } => write!(f, "m{start}:{end}"),
+            PafIon::Immonium(residue) => write!(f, "I{residue}"),
+            PafIon::Precursor => write!(f, "p"),
+            PafIon::Reporter(name) => write!(f, "{name}"),
+            PafIon::Formula(formula) => write!(f, "{}", subscript_digits(formula)),
+            PafIon::Named(name) => write!(f, "{name}"),
+            PafIon::Unknown(Some(i)) => write!(f, "?{i}"),
+            PafIon::Unknown(None) => write!(f, "?"),
+        }
+    }
+}
+
+/// A neutral gain or loss, like `-H2O`, `+CO` or `-[Phospho]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeutralLoss {
+    /// `-1` for a loss, `1` for a gain
+    pub sign: i8,
+    pub count: usize,
+    /// A chemical formula or, if it was written in brackets, a named loss
+    pub name: String,
+}
+
+impl Display for NeutralLoss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.sign < 0 { '-' } else { '+' };
+        if self.count > 1 {
+            write!(f, "{sign}{}{}", self.count, subscript_digits(&self.name))
+        } else {
+            write!(f, "{sign}{}", subscript_digits(&self.name))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MassError {
+    Da(f64),
+    PPM(f64),
+}
+
+impl Display for MassError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MassError::Da(e) => write!(f, "{e} Da"),
+            MassError::PPM(e) => write!(f, "{e} ppm"),
+        }
+    }
+}
+
+/// A single explanation of a peak
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakAnnotation {
+    /// Whether this is an auxiliary annotation, marked with a leading `&`
+    pub auxiliary: bool,
+    /// Which analyte this ion came from when several were fragmented together
+    pub analyte: Option<usize>,
+    pub ion: PafIon,
+    pub neutral_losses: Vec<NeutralLoss>,
+    /// The number of heavy isotopes, e.g. `+i` or `+2i`
+    pub isotope: i32,
+    pub adduct: Option<String>,
+    pub charge: i32,
+    pub mass_error: Option<MassError>,
+    pub confidence: Option<f64>,
+}
+
+impl PeakAnnotation {
+    pub fn color(&self) -> &'static str {
+        self.ion.color()
+    }
+
+    /// A compact human readable label with subscript formula counts and superscript charges
+    pub fn label(&self) -> String {
+        let mut label = self.ion.to_string();
+        for loss in self.neutral_losses.iter() {
+            label.push_str(&loss.to_string());
+        }
+        match self.isotope {
+            0 => {}
+            1 => label.push_str("+i"),
+            -1 => label.push_str("-i"),
+            n => label.push_str(&format!("{n:+}i")),
+        }
+        label.push_str(&charge_superscript(self.charge));
+        if let Some(error) = self.mass_error {
+            label.push_str(&format!(" ({error})"));
+        }
+        label
+    }
+
+    /// Parse a comma-separated list of annotations for a single peak
+    pub fn parse_list(text: &str) -> Result<Vec<Self>, MzPafError> {
+        if text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        Scanner::new(text).annotation_list()
+    }
+}
+
+impl FromStr for PeakAnnotation {
+    type Err = MzPafError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scanner = Scanner::new(s);
+        let annotation = scanner.annotation()?;
+        scanner.finish()?;
+        Ok(annotation)
+    }
+}
+
+/// Replace the digits in a chemical formula with subscripts
+fn subscript_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '0'..='9' => char::from_u32('₀' as u32 + c.to_digit(10).unwrap()).unwrap(),
+            _ => c,
+        })
+        .collect()
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, position: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self) -> MzPafError {
+        match self.peek() {
+            Some(c) => MzPafError::UnexpectedCharacter(c, self.position),
+            None => MzPafError::UnexpectedEnd,
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.advance();
+        }
+        &self.text[start..self.position]
+    }
+
+    fn integer(&mut self) -> Option<usize> {
+        self.take_while(|c| c.is_ascii_digit()).parse().ok()
+    }
+
+    fn number(&mut self) -> Result<f64, MzPafError> {
+        let text = self.take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e'));
+        text.parse()
+            .map_err(|_| MzPafError::InvalidNumber(text.to_string()))
+    }
+
+    /// Read the text between `open` and `close`, where `open` is the next character
+    fn delimited(&mut self, open: char, close: char) -> Result<&'a str, MzPafError> {
+        let start = self.position;
+        if !self.eat(open) {
+            return Err(self.unexpected());
+        }
+        let content_start = self.position;
+        let mut depth = 1;
+        while let Some(c) = self.advance() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(&self.text[content_start..self.position - close.len_utf8()]);
+                }
+            }
+        }
+        Err(MzPafError::UnclosedBracket(start))
+    }
+
+    fn finish(&self) -> Result<(), MzPafError> {
+        if self.position < self.text.len() {
+            Err(self.unexpected())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn annotation_list(&mut self) -> Result<Vec<PeakAnnotation>, MzPafError> {
+        let mut annotations = vec![self.annotation()?];
+        while self.eat(',') {
+            self.take_while(char::is_whitespace);
+            annotations.push(self.annotation()?);
+        }
+        self.finish()?;
+        Ok(annotations)
+    }
+
+    fn ion(&mut self) -> Result<PafIon, MzPafError> {
+        let start = self.position;
+        let ion = match self.advance().ok_or(MzPafError::Empty)? {
+            c @ ('a' | 'b' | 'c' | 'x' | 'y' | 'z') => {
+                let ion_type = match c {
+                    'a' => IonType::A,
+                    'b' => IonType::B,
+                    'c' => IonType::C,
+                    'x' => IonType::X,
+                    'y' => IonType::Y,
+                    _ => IonType::Z,
+                };
+                // Radical variants like `z.` are drawn with the same series
+                self.eat('.');
+                let ordinal = self.integer().ok_or_else(|| self.unexpected())?;
+                PafIon::Series { ion_type, ordinal }
+            }
+            'm' => {
+                let start = self.integer().ok_or_else(|| self.unexpected())?;
+                if !self.eat(':') {
+                    return Err(self.unexpected());
+                }
+                let end = self.integer().ok_or_else(|| self.unexpected())?;
+                PafIon::Internal { start, end }
+            }
+            'I' => {
+                let residue = self.take_while(|c| c.is_ascii_uppercase());
+                if residue.is_empty() {
+                    return Err(self.unexpected());
+                }
+                let mut residue = residue.to_string();
+                if self.peek() == Some('[') {
+                    residue = format!("{residue}[{}]", self.delimited('[', ']')?);
+                }
+                PafIon::Immonium(residue)
+            }
+            'p' => PafIon::Precursor,
+            'r' => PafIon::Reporter(self.delimited('[', ']')?.to_string()),
+            'f' => PafIon::Formula(self.delimited('{', '}')?.to_string()),
+            '_' => PafIon::Named(self.delimited('{', '}')?.to_string()),
+            '?' => PafIon::Unknown(self.integer()),
+            c => return Err(MzPafError::UnexpectedCharacter(c, start)),
+        };
+        Ok(ion)
+    }
+
+    fn annotation(&mut self) -> Result<PeakAnnotation, MzPafError> {
+        if self.peek().is_none() {
+            return Err(MzPafError::Empty);
+        }
+        let auxiliary = self.eat('&');
+        let checkpoint = self.position;
+        let analyte = match self.integer() {
+            Some(analyte) if self.eat('@') => Some(analyte),
+            _ => {
+                self.position = checkpoint;
+                None
+            }
+        };
+        let ion = self.ion()?;
+
+        let mut neutral_losses = Vec::new();
+        let mut isotope = 0;
+        while let Some(sign @ ('+' | '-')) = self.peek() {
+            self.advance();
+            let sign: i8 = if sign == '-' { -1 } else { 1 };
+            let count = self.integer();
+            if self.eat('i') {
+                // An optional isotope label like `13C` may follow
+                self.take_while(|c| c.is_ascii_alphanumeric());
+                isotope += sign as i32 * count.unwrap_or(1) as i32;
+                continue;
+            }
+            let name = if self.peek() == Some('[') {
+                self.delimited('[', ']')?.to_string()
+            } else {
+                let formula = self.take_while(|c| c.is_ascii_alphanumeric());
+                if formula.is_empty() {
+                    return Err(self.unexpected());
+                }
+                formula.to_string()
+            };
+            neutral_losses.push(NeutralLoss {
+                sign,
+                count: count.unwrap_or(1),
+                name,
+            });
+        }
+
+        let adduct = if self.peek() == Some('[') {
+            Some(self.delimited('[', ']')?.to_string())
+        } else {
+            None
+        };
+
+        let charge = if self.eat('^') {
+            self.integer().ok_or_else(|| self.unexpected())? as i32
+        } else {
+            1
+        };
+
+        let mass_error = if self.eat('/') {
+            let error = self.number()?;
+            if self.text[self.position..].starts_with("ppm") {
+                self.position += 3;
+                Some(MassError::PPM(error))
+            } else {
+                Some(MassError::Da(error))
+            }
+        } else {
+            None
+        };
+
+        let confidence = if self.eat('*') {
+            Some(self.number()?)
+        } else {
+            None
+        };
+
+        Ok(PeakAnnotation {
+            auxiliary,
+            analyte,
+            ion,
+            neutral_losses,
+            isotope,
+            adduct,
+            charge,
+            mass_error,
+            confidence,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_mzpaf() {
+        let annot: PeakAnnotation = "b5^2/1.2ppm".parse().unwrap();
+        assert_eq!(
+            annot.ion,
+            PafIon::Series {
+                ion_type: IonType::B,
+                ordinal: 5
+            }
+        );
+        assert_eq!(annot.charge, 2);
+        assert_eq!(annot.mass_error, Some(MassError::PPM(1.2)));
+        assert_eq!(annot.label(), "b5²⁺ (1.2 ppm)");
+
+        let annot: PeakAnnotation = "y7-H2O".parse().unwrap();
+        assert_eq!(annot.neutral_losses.len(), 1);
+        assert_eq!(annot.label(), "y7-H₂O");
+        assert_eq!(annot.color(), IonType::Y.color());
+
+        let annot: PeakAnnotation = "p^3".parse().unwrap();
+        assert_eq!(annot.ion, PafIon::Precursor);
+        assert_eq!(annot.charge, 3);
+
+        let annots =
+            PeakAnnotation::parse_list("2@b3-[Phospho]+i/-0.002*0.75, &IY, m2:4, r[TMT127N]")
+                .unwrap();
+        assert_eq!(annots.len(), 4);
+        assert_eq!(annots[0].analyte, Some(2));
+        assert_eq!(annots[0].isotope, 1);
+        assert_eq!(annots[0].mass_error, Some(MassError::Da(-0.002)));
+        assert_eq!(annots[0].confidence, Some(0.75));
+        assert!(annots[1].auxiliary);
+        assert_eq!(annots[2].ion, PafIon::Internal { start: 2, end: 4 });
+        assert_eq!(annots[3].ion, PafIon::Reporter("TMT127N".to_string()));
+
+        assert_eq!(
+            "q5".parse::<PeakAnnotation>(),
+            Err(MzPafError::UnexpectedCharacter('q', 0))
+        );
+        assert!("b".parse::<PeakAnnotation>().is_err());
+    }
+}
@@ -0,0 +1,578 @@
+//! A minimal [ProForma](https://github.com/HUPO-PSI/ProForma) peptide reader and fragment
+//! ion calculator for annotating spectra.
+//!
+//! Only the parts of the notation needed to compute fragment masses are supported: residues,
+//! mass shift and named modifications on residues and termini, global fixed modifications
+//! and a trailing precursor charge.
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use mzpeaks::{CentroidLike, MZLocated, MZPeakSetType, PeakCollection, Tolerance};
+
+pub const PROTON: f64 = 1.00727646677;
+pub const WATER: f64 = 18.0105646837;
+pub const AMMONIA: f64 = 17.0265491015;
+pub const CARBON_MONOXIDE: f64 = 27.9949146221;
+pub const HYDROGEN: f64 = 1.00782503207;
+
+/// The monoisotopic mass of an amino acid residue
+pub fn residue_mass(symbol: char) -> Option<f64> {
+    let mass = match symbol {
+        'G' => 57.02146372,
+        'A' => 71.03711379,
+        'S' => 87.03202841,
+        'P' => 97.05276385,
+        'V' => 99.06841391,
+        'T' => 101.04767847,
+        'C' => 103.00918478,
+        'L' | 'I' => 113.08406398,
+        'N' => 114.04292744,
+        'D' => 115.02694303,
+        'Q' => 128.05857751,
+        'K' => 128.09496302,
+        'E' => 129.04259309,
+        'M' => 131.04048491,
+        'H' => 137.05891186,
+        'F' => 147.06841391,
+        'U' => 150.95363559,
+        'R' => 156.10111103,
+        'Y' => 163.06332853,
+        'W' => 186.07931295,
+        'O' => 237.14772686,
+        _ => return None,
+    };
+    Some(mass)
+}
+
+/// Look up the mass shift of a commonly used modification by its Unimod name or accession
+pub fn modification_mass(name: &str) -> Option<f64> {
+    let mass = match name.to_ascii_lowercase().as_str() {
+        "oxidation" | "unimod:35" => 15.994915,
+        "carbamidomethyl" | "unimod:4" => 57.021464,
+        "phospho" | "unimod:21" => 79.966331,
+        "acetyl" | "unimod:1" => 42.010565,
+        "amidated" | "unimod:2" => -0.984016,
+        "deamidated" | "unimod:7" => 0.984016,
+        "methyl" | "unimod:34" => 14.01565,
+        "dimethyl" | "unimod:36" => 28.0313,
+        "trimethyl" | "unimod:37" => 42.04695,
+        "carbamyl" | "unimod:5" => 43.005814,
+        "gln->pyro-glu" | "unimod:28" => -17.026549,
+        "glu->pyro-glu" | "unimod:27" => -18.010565,
+        "tmt6plex" | "unimod:737" => 229.162932,
+        _ => return None,
+    };
+    Some(mass)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProFormaError {
+    Empty,
+    UnknownResidue(char, usize),
+    UnknownModification(String),
+    UnclosedBracket(usize),
+    UnexpectedCharacter(char, usize),
+    InvalidCharge(String),
+}
+
+impl Display for ProFormaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProFormaError::Empty => write!(f, "The peptide sequence is empty"),
+            ProFormaError::UnknownResidue(c, i) => {
+                write!(f, "Unknown amino acid {c:?} at position {i}")
+            }
+            ProFormaError::UnknownModification(m) => write!(f, "Unknown modification {m:?}"),
+            ProFormaError::UnclosedBracket(i) => {
+                write!(f, "Unclosed bracket starting at position {i}")
+            }
+            ProFormaError::UnexpectedCharacter(c, i) => {
+                write!(f, "Unexpected character {c:?} at position {i}")
+            }
+            ProFormaError::InvalidCharge(s) => write!(f, "Invalid charge state {s:?}"),
+        }
+    }
+}
+
+impl Error for ProFormaError {}
+
+/// Resolve the text between a pair of square brackets to a mass shift
+fn parse_modification(text: &str) -> Result<f64, ProFormaError> {
+    // A modification may list several alternative descriptions separated by `|`,
+    // use the first one we understand.
+    for part in text.split('|') {
+        let part = part.trim();
+        let value = part
+            .strip_prefix("Obs:")
+            .or_else(|| part.strip_prefix("U:"))
+            .unwrap_or(part);
+        if let Ok(mass) = value.parse::<f64>() {
+            return Ok(mass);
+        }
+        if let Some(mass) = modification_mass(value) {
+            return Ok(mass);
+        }
+    }
+    Err(ProFormaError::UnknownModification(text.to_string()))
+}
+
+/// An amino acid in a [`Peptide`]. Only residues with a known mass can be created.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Residue {
+    symbol: char,
+    /// The total mass shift of all modifications on this residue
+    modification: f64,
+}
+
+impl Residue {
+    /// Create a residue, or `None` if `symbol` isn't a known amino acid
+    pub fn new(symbol: char, modification: f64) -> Option<Self> {
+        residue_mass(symbol).map(|_| Self {
+            symbol,
+            modification,
+        })
+    }
+
+    pub fn symbol(&self) -> char {
+        self.symbol
+    }
+
+    /// The total mass shift of all modifications on this residue
+    pub fn modification(&self) -> f64 {
+        self.modification
+    }
+
+    pub fn mass(&self) -> f64 {
+        residue_mass(self.symbol).expect("residues are only created with known symbols")
+            + self.modification
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Peptide {
+    pub residues: Vec<Residue>,
+    pub n_term: f64,
+    pub c_term: f64,
+    pub charge: Option<i32>,
+}
+
+struct Reader<'a> {
+    chars: Vec<(usize, char)>,
+    position: usize,
+    text: &'a str,
+}
+
+impl<'a> Reader<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.char_indices().collect(),
+            position: 0,
+            text,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).map(|(_, c)| *c)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).map(|(_, c)| *c)
+    }
+
+    fn offset(&self) -> usize {
+        self.chars
+            .get(self.position)
+            .map(|(i, _)| *i)
+            .unwrap_or(self.text.len())
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ProFormaError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(ProFormaError::UnexpectedCharacter(c, self.offset())),
+            None => Err(ProFormaError::UnexpectedCharacter(expected, self.offset())),
+        }
+    }
+
+    /// Read the text between a pair of delimiters, allowing the pair to nest
+    fn delimited(&mut self, open: char, close: char) -> Result<&'a str, ProFormaError> {
+        let start = self.offset();
+        self.expect(open)?;
+        let content_start = self.offset();
+        let mut depth = 1;
+        while let Some(c) = self.advance() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    let content_end = self.chars[self.position - 1].0;
+                    return Ok(&self.text[content_start..content_end]);
+                }
+            }
+        }
+        Err(ProFormaError::UnclosedBracket(start))
+    }
+
+    /// Read any number of consecutive bracketed modifications and sum their masses
+    fn modifications(&mut self) -> Result<f64, ProFormaError> {
+        let mut total = 0.0;
+        while self.peek() == Some('[') {
+            total += parse_modification(self.delimited('[', ']')?)?;
+        }
+        Ok(total)
+    }
+}
+
+impl Peptide {
+    pub fn from_proforma(text: &str) -> Result<Self, ProFormaError> {
+        let mut reader = Reader::new(text.trim());
+        let mut fixed: Vec<(f64, Vec<char>)> = Vec::new();
+        let mut peptide = Peptide::default();
+
+        while reader.peek() == Some('<') {
+            let rule = reader.delimited('<', '>')?;
+            let (modification, targets) = rule
+                .rsplit_once('@')
+                .ok_or_else(|| ProFormaError::UnknownModification(rule.to_string()))?;
+            let modification = modification
+                .strip_prefix('[')
+                .and_then(|m| m.strip_suffix(']'))
+                .unwrap_or(modification);
+            let mass = parse_modification(modification)?;
+            fixed.push((
+                mass,
+                targets
+                    .split(',')
+                    .filter_map(|t| t.trim().chars().next())
+                    .collect(),
+            ));
+        }
+
+        if reader.peek() == Some('[') {
+            peptide.n_term = reader.modifications()?;
+            reader.expect('-')?;
+        }
+
+        while let Some(c) = reader.peek() {
+            if c == '-' || c == '/' {
+                break;
+            }
+            let offset = reader.offset();
+            let Some(residue) = Residue::new(c, 0.0) else {
+                return Err(ProFormaError::UnknownResidue(c, offset));
+            };
+            reader.advance();
+            let modification = reader.modifications()?
+                + fixed
+                    .iter()
+                    .filter(|(_, targets)| targets.contains(&c))
+                    .map(|(mass, _)| *mass)
+                    .sum::<f64>();
+            peptide.residues.push(Residue {
+                modification,
+                ..residue
+            });
+        }
+
+        if peptide.residues.is_empty() {
+            return Err(ProFormaError::Empty);
+        }
+
+        if reader.peek() == Some('-') && reader.peek_at(1) == Some('[') {
+            reader.advance();
+            peptide.c_term = reader.modifications()?;
+        }
+
+        if reader.peek() == Some('/') {
+            reader.advance();
+            let start = reader.offset();
+            let charge_text = &text.trim()[start..];
+            let charge = charge_text
+                .parse()
+                .map_err(|_| ProFormaError::InvalidCharge(charge_text.to_string()))?;
+            peptide.charge = Some(charge);
+            reader.position = reader.chars.len();
+        }
+
+        if let Some(c) = reader.peek() {
+            return Err(ProFormaError::UnexpectedCharacter(c, reader.offset()));
+        }
+        Ok(peptide)
+    }
+
+    pub fn len(&self) -> usize {
+        self.residues.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.residues.is_empty()
+    }
+
+    /// The unmodified amino acid sequence
+    pub fn sequence(&self) -> String {
+        self.residues.iter().map(|r| r.symbol()).collect()
+    }
+
+    /// The neutral monoisotopic mass of the whole peptide
+    pub fn monoisotopic_mass(&self) -> f64 {
+        self.residues.iter().map(|r| r.mass()).sum::<f64>() + self.n_term + self.c_term + WATER
+    }
+
+    pub fn precursor_mz(&self, charge: i32) -> f64 {
+        mass_charge_ratio(self.monoisotopic_mass(), charge)
+    }
+
+    /// Compute the m/z of every fragment of `ion_types` at charges 1 through `max_charge`
+    pub fn fragments(&self, ion_types: &[IonType], max_charge: i32) -> Vec<Fragment> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let n = self.len();
+        let mut prefix = self.n_term;
+        let prefix_masses: Vec<f64> = self.residues[..n.saturating_sub(1)]
+            .iter()
+            .map(|r| {
+                prefix += r.mass();
+                prefix
+            })
+            .collect();
+        let mut suffix = self.c_term + WATER;
+        let suffix_masses: Vec<f64> = self.residues[1..]
+            .iter()
+            .rev()
+            .map(|r| {
+                suffix += r.mass();
+                suffix
+            })
+            .collect();
+
+        let mut fragments = Vec::new();
+        for ion_type in ion_types.iter().copied() {
+            let masses = if ion_type.is_n_terminal() {
+                &prefix_masses
+            } else {
+                &suffix_masses
+            };
+            for (i, mass) in masses.iter().enumerate() {
+                let mass = mass + ion_type.mass_shift();
+                for charge in 1..=max_charge.max(1) {
+                    fragments.push(Fragment {
+                        ion_type,
+                        ordinal: i + 1,
+                        charge,
+                        mz: mass_charge_ratio(mass, charge),
+                    })
+                }
+            }
+        }
+        fragments
+    }
+}
+
+impl FromStr for Peptide {
+    type Err = ProFormaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_proforma(s)
+    }
+}
+
+pub fn mass_charge_ratio(neutral_mass: f64, charge: i32) -> f64 {
+    (neutral_mass + PROTON * charge as f64) / charge as f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IonType {
+    A,
+    B,
+    C,
+    X,
+    Y,
+    Z,
+}
+
+impl IonType {
+    pub fn is_n_terminal(&self) -> bool {
+        matches!(self, Self::A | Self::B | Self::C)
+    }
+
+    /// The mass difference from the sum of the residues, plus water for C-terminal ions
+    pub fn mass_shift(&self) -> f64 {
+        match self {
+            IonType::A => -CARBON_MONOXIDE,
+            IonType::B => 0.0,
+            IonType::C => AMMONIA,
+            IonType::X => CARBON_MONOXIDE - 2.0 * HYDROGEN,
+            IonType::Y => 0.0,
+            // The z-dot ion
+            IonType::Z => -AMMONIA + HYDROGEN,
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            IonType::A => 'a',
+            IonType::B => 'b',
+            IonType::C => 'c',
+            IonType::X => 'x',
+            IonType::Y => 'y',
+            IonType::Z => 'z',
+        }
+    }
+
+    /// The color used to draw this ion series
+    pub fn color(&self) -> &'static str {
+        match self {
+            IonType::A => "darkgreen",
+            IonType::B => "steelblue",
+            IonType::C => "darkorange",
+            IonType::X => "goldenrod",
+            IonType::Y => "firebrick",
+            IonType::Z => "blueviolet",
+        }
+    }
+}
+
+/// Format a charge state as a superscript suffix, empty for singly charged ions
+pub fn charge_superscript(charge: i32) -> String {
+    if charge.abs() == 1 {
+        return String::new();
+    }
+    let digits: String = charge
+        .abs()
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            '0' => '⁰',
+            '1' => '¹',
+            '2' => '²',
+            '3' => '³',
+            '4' => '⁴',
+            '5' => '⁵',
+            '6' => '⁶',
+            '7' => '⁷',
+            '8' => '⁸',
+            _ => '⁹',
+        })
+        .collect();
+    format!("{digits}{}", if charge > 0 { '⁺' } else { '⁻' })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fragment {
+    pub ion_type: IonType,
+    /// The number of residues in the fragment
+    pub ordinal: usize,
+    pub charge: i32,
+    pub mz: f64,
+}
+
+impl Fragment {
+    pub fn label(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.ion_type.symbol(),
+            self.ordinal,
+            charge_superscript(self.charge)
+        )
+    }
+}
+
+/// A theoretical fragment paired with the observed peak it was matched to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FragmentMatch {
+    pub fragment: Fragment,
+    pub mz: f64,
+    pub intensity: f32,
+}
+
+/// Match each fragment to the most intense peak within `tolerance` of it
+pub fn match_fragments<C: CentroidLike>(
+    fragments: &[Fragment],
+    peaks: &MZPeakSetType<C>,
+    tolerance: Tolerance,
+) -> Vec<FragmentMatch> {
+    fragments
+        .iter()
+        .filter_map(|fragment| {
+            peaks
+                .all_peaks_for(fragment.mz, tolerance)
+                .iter()
+                .max_by(|a, b| a.intensity().total_cmp(&b.intensity()))
+                .map(|peak| FragmentMatch {
+                    fragment: *fragment,
+                    mz: peak.mz(),
+                    intensity: peak.intensity(),
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_proforma() {
+        let peptide = Peptide::from_proforma("[Acetyl]-PEM[Oxidation]TC[+57.021464]K/2").unwrap();
+        assert_eq!(peptide.sequence(), "PEMTCK");
+        assert_eq!(peptide.charge, Some(2));
+        assert!((peptide.n_term - 42.010565).abs() < 1e-6);
+        assert!((peptide.residues[2].modification() - 15.994915).abs() < 1e-6);
+
+        let fixed = Peptide::from_proforma("<[Carbamidomethyl]@C>PEMTCK").unwrap();
+        assert!((fixed.residues[4].modification() - 57.021464).abs() < 1e-6);
+
+        assert_eq!(
+            Peptide::from_proforma("PEPTIDEB"),
+            Err(ProFormaError::UnknownResidue('B', 7))
+        );
+        assert_eq!(Residue::new('B', 0.0), None);
+        assert!((Residue::new('G', 1.0).unwrap().mass() - 58.021464).abs() < 1e-6);
+        assert!(matches!(
+            Peptide::from_proforma("PEM[Oxidation"),
+            Err(ProFormaError::UnclosedBracket(3))
+        ));
+        assert!(matches!(
+            Peptide::from_proforma("PEM[Foo]"),
+            Err(ProFormaError::UnknownModification(_))
+        ));
+    }
+
+    #[test]
+    fn test_fragments() {
+        let peptide: Peptide = "PEPTIDE".parse().unwrap();
+        assert!((peptide.monoisotopic_mass() - 799.359964).abs() < 1e-4);
+
+        let fragments = peptide.fragments(&[IonType::B, IonType::Y], 1);
+        assert_eq!(fragments.len(), 12);
+        let b2 = fragments.iter().find(|f| f.label() == "b2").unwrap();
+        assert!((b2.mz - 227.102633).abs() < 1e-4);
+        let y1 = fragments.iter().find(|f| f.label() == "y1").unwrap();
+        assert!((y1.mz - 148.060434).abs() < 1e-4);
+
+        let doubly = peptide.fragments(&[IonType::Y], 2);
+        assert_eq!(doubly[1].label(), "y1²⁺");
+
+        assert!(Peptide::default()
+            .fragments(&[IonType::B, IonType::Y], 1)
+            .is_empty());
+
+        let peaks: MZPeakSetType<mzpeaks::CentroidPeak> = MZPeakSetType::new(vec![
+            mzpeaks::CentroidPeak::new(148.0605, 100.0, 0),
+            mzpeaks::CentroidPeak::new(227.1030, 50.0, 1),
+            mzpeaks::CentroidPeak::new(500.0, 10.0, 2),
+        ]);
+        let matches = match_fragments(&fragments, &peaks, Tolerance::PPM(10.0));
+        assert_eq!(matches.len(), 2);
+    }
+}
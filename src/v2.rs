@@ -1,11 +1,43 @@
 mod chart;
 mod chart_regions;
+mod figure;
+mod fragments;
+mod heading;
+mod heatmap;
+mod inset;
+mod labels;
+mod layout;
+mod legend;
 mod series;
 
-pub use chart::{SpectrumSVG, FeatureSVG};
-pub use chart_regions::{AxisOrientation, AxisProps, AxisTickLabelStyle, Canvas, TextProps};
+pub use chart::{
+    chromatogram_axis_label, ion_mobility_axis_label, FeatureSVG, HeatmapSVG, IonMobilityFrameSVG,
+    MirrorSpectrumSVG, SpectrumSVG, WaterfallSVG, WaterfallTrace, DEFAULT_PANEL_GAP,
+    DEFAULT_PROJECTION_SIZE, DEFAULT_WATERFALL_OFFSET,
+};
+pub use chart_regions::{
+    AxisOrientation, AxisProps, AxisTickLabelStyle, Canvas, DrawBox, GridlineStyle, Magnification,
+    SeriesOffset, Sides, TextProps, TickFormatter, TimeUnit,
+};
+pub use figure::{Figure, Panel, DEFAULT_FIGURE_SPACING};
+pub use fragments::{FragmentAnnotation, FragmentSeries, PeakAnnotationSeries, PeptideLadder};
+pub use heading::{Heading, SpectrumHeader, DEFAULT_HEADER_TEMPLATE};
+pub use heatmap::{
+    ColorBar, Colormap, HeatmapSeries, DEFAULT_COLOR_BAR_GAP, DEFAULT_COLOR_BAR_WIDTH,
+};
+pub use inset::{Inset, InsetCorner};
+pub use labels::{
+    DeconvolutedLabelFields, DeconvolutedLabels, LabelBox, LabelLayout, PeakLabelFormat,
+    PeakLabels, PeakSelection,
+};
+#[cfg(any(feature = "png", feature = "pdf"))]
+pub use layout::FontTextMetrics;
+pub use layout::{default_text_metrics, ApproximateTextMetrics, TextMetrics, DEFAULT_FONT_SIZE};
+pub use legend::{Legend, LegendEntry, LegendPlacement, LegendSwatch};
 pub use series::{
-    peaks_to_arrays, AsSeries, CentroidSeries, ContinuousSeries, DeconvolutedCentroidSeries,
-    LineSeries, PlotSeries, SeriesDescription, AnnotationSeries, TraceSeries, ColorCycle,
-    ScatterSeries, DEFAULT_COLOR_CYCLE, PrecursorSeries, PlotPeak,
+    chromatogram_feature, chromatogram_label, peaks_to_arrays, AnnotationSeries, AreaSeries,
+    AsSeries, CentroidSeries, ChromatogramSeries, ChromatogramStyle, ColorCycle, ContinuousSeries,
+    DeconvolutedCentroidSeries, EnvelopeStyle, IsotopicEnvelope, IsotopicEnvelopeSeries,
+    LineSeries, PlotPeak, PlotSeries, PrecursorSeries, ScatterSeries, SeriesDescription,
+    TraceSeries, DEFAULT_COLOR_CYCLE,
 };
@@ -8,24 +8,47 @@ use num_traits::Float;
 
 use mzdata::{
     self,
+    params::Unit,
     prelude::*,
-    spectrum::{BinaryArrayMap, MultiLayerSpectrum, SignalContinuity},
+    spectrum::{
+        bindata::BinaryArrayMap3D, ArrayType, BinaryArrayMap, Chromatogram, ChromatogramLike,
+        ChromatogramType, MultiLayerSpectrum, RefPeakDataLevel, SignalContinuity,
+    },
 };
 
 use mzpeaks::{
     feature::FeatureLike,
-    {CentroidLike, DeconvolutedCentroidLike, MZLocated, MZPeakSetType, MassPeakSetType},
+    {
+        CentroidLike, DeconvolutedCentroidLike, IonMobility, KnownCharge, MZLocated, MZPeakSetType,
+        Mass, MassPeakSetType, Tolerance, MZ,
+    },
 };
-use svg::node::element::{Group, Style as CSSStyle};
+use svg::node::element::{Group, Line, Style as CSSStyle};
 use svg::{Document, Node};
 
-use super::chart_regions::{AxisOrientation, AxisProps, AxisTickLabelStyle, Canvas};
+use super::chart_regions::{
+    AxisOrientation, AxisProps, AxisTickLabelStyle, Canvas, HorizontalAlignment, Magnification,
+    SeriesOffset, Sides, TextProps, TimeUnit,
+};
+use super::fragments::{FragmentAnnotation, FragmentSeries, PeptideLadder};
+use super::heading::{Heading, SpectrumHeader};
+use super::heatmap::{ColorBar, Colormap, HeatmapSeries};
+use super::inset::Inset;
+use super::labels::{DeconvolutedLabels, LabelBox, PeakLabels};
+#[cfg(any(feature = "png", feature = "pdf"))]
+use super::layout::system_font_database;
+use super::layout::{default_text_metrics, DEFAULT_FONT_SIZE};
+use super::legend::{Legend, LegendEntry};
 use super::series::{
-    CentroidSeries, ColorCycle, ContinuousSeries, DeconvolutedCentroidSeries, PlotSeries,
-    SeriesDescription,
+    chromatogram_feature, AreaSeries, CentroidSeries, ColorCycle, ContinuousSeries,
+    DeconvolutedCentroidSeries, IsotopicEnvelopeSeries, LineSeries, PlotSeries, SeriesDescription,
 };
 
-use crate::{AsSeries, CoordinateRange};
+use crate::isotopes::IsotopeModel;
+use crate::linear::segments_from_positions;
+use crate::peptide::FragmentMatch;
+use crate::xic::Xic;
+use crate::{AsSeries, CoordinateRange, ScaleType};
 
 pub trait SVGCanvas {
     fn get_canvas(&self) -> &Canvas<f64, f32>;
@@ -54,21 +77,10 @@ pub trait SVGCanvas {
 
     #[cfg(feature = "png")]
     fn write_png<W: Write>(&self, stream: &mut W) -> io::Result<()> {
-        use std::sync::Arc;
-
         let mut buf = Vec::new();
         self.write(&mut buf)?;
-        let mut fontdb = resvg::usvg::fontdb::Database::new();
-        fontdb.load_system_fonts();
-
-        fontdb.set_serif_family("Times New Roman");
-        fontdb.set_sans_serif_family("Arial");
-        fontdb.set_cursive_family("Comic Sans MS");
-        fontdb.set_fantasy_family("Impact");
-        fontdb.set_monospace_family("Courier New");
-
         let svg_opts = resvg::usvg::Options {
-            fontdb: Arc::new(fontdb),
+            fontdb: system_font_database(),
             ..Default::default()
         };
 
@@ -101,8 +113,6 @@ pub trait SVGCanvas {
 
     #[cfg(feature = "pdf")]
     fn write_pdf<W: Write>(&self, stream: &mut W) -> io::Result<()> {
-        use std::sync::Arc;
-
         let mut buf = Vec::new();
         self.write(&mut buf)?;
 
@@ -110,17 +120,8 @@ pub trait SVGCanvas {
         let mut page_opts = svg2pdf::PageOptions::default();
         page_opts.dpi = 180.0;
 
-        let mut fontdb = fontdb::Database::new();
-        fontdb.load_system_fonts();
-
-        fontdb.set_serif_family("Times New Roman");
-        fontdb.set_sans_serif_family("Arial");
-        fontdb.set_cursive_family("Comic Sans MS");
-        fontdb.set_fantasy_family("Impact");
-        fontdb.set_monospace_family("Courier New");
-
         let svg_opts = svg2pdf::usvg::Options {
-            fontdb: Arc::new(fontdb),
+            fontdb: system_font_database(),
             ..Default::default()
         };
 
@@ -143,11 +144,25 @@ pub struct SpectrumSVG {
     pub colors: ColorCycle,
     pub xticks: AxisProps<f64>,
     pub yticks: AxisProps<f32>,
+    pub secondary_xticks: Option<AxisProps<f64>>,
+    pub secondary_yticks: Option<AxisProps<f32>>,
     pub x_range: Option<CoordinateRange<f64>>,
     pub y_range: Option<CoordinateRange<f32>>,
     pub finished: bool,
     pub series: HashMap<String, Vec<SeriesDescription>>,
+    /// The series type of each description added to `series`, in the order they were added
+    pub series_order: Vec<String>,
     pub custom_css: Option<String>,
+    /// How to label deconvoluted peaks, if at all
+    pub deconvoluted_labels: Option<DeconvolutedLabels>,
+    /// How to label centroid peaks with their m/z, if at all
+    pub peak_labels: Option<PeakLabels>,
+    /// The legend to draw, filled in with every registered series when rendered
+    pub legend: Option<Legend>,
+    /// The title and subtitle drawn above the chart
+    pub heading: Heading,
+    /// The boxes of the labels drawn so far, which later labels are kept clear of
+    pub placed_labels: Vec<LabelBox>,
 }
 
 impl Default for SpectrumSVG {
@@ -162,11 +177,19 @@ impl Default for SpectrumSVG {
                 .label("Intensity")
                 .tick_format(AxisTickLabelStyle::SciNot(2))
                 .id("y-axis"),
+            secondary_xticks: None,
+            secondary_yticks: None,
             x_range: Default::default(),
             y_range: Default::default(),
             finished: false,
             series: HashMap::new(),
+            series_order: Vec::new(),
             custom_css: None,
+            deconvoluted_labels: None,
+            peak_labels: None,
+            legend: None,
+            heading: Heading::default(),
+            placed_labels: Vec::new(),
         }
     }
 }
@@ -206,6 +229,12 @@ impl SpectrumSVG {
         &mut self.canvas
     }
 
+    /// Fit the canvas to `x_range` and `y_range`, forgetting the labels placed at the old scales
+    fn update_scales(&mut self, x_range: CoordinateRange<f64>, y_range: CoordinateRange<f32>) {
+        self.canvas.update_scales(x_range, y_range);
+        self.placed_labels.clear();
+    }
+
     pub fn add_raw(&mut self, group: Group) {
         self.canvas.push_layer(group);
     }
@@ -248,8 +277,7 @@ impl SpectrumSVG {
             x.end = x.end.max(max_mz);
         }
 
-        self.canvas
-            .update_scales(self.x_range.clone().unwrap(), self.y_range.clone().unwrap());
+        self.update_scales(self.x_range.clone().unwrap(), self.y_range.clone().unwrap());
 
         self
     }
@@ -268,9 +296,91 @@ impl SpectrumSVG {
             Bound::Unbounded => {}
         }
 
-        self.canvas
-            .update_scales(self.x_range.clone().unwrap(), self.y_range.clone().unwrap());
+        self.update_scales(self.x_range.clone().unwrap(), self.y_range.clone().unwrap());
+
+        self
+    }
+
+    /// Break the m/z axis into several `segments`, each drawn in its own share of the canvas
+    /// width with break markers between them. The x-axis range is set to span all of the
+    /// segments.
+    ///
+    /// This must be called before drawing any series. Passing no segments restores a
+    /// contiguous axis.
+    pub fn x_segments<R: Into<CoordinateRange<f64>>>(
+        &mut self,
+        segments: impl IntoIterator<Item = R>,
+    ) -> &mut Self {
+        let segments: Vec<CoordinateRange<f64>> = segments.into_iter().map(|s| s.into()).collect();
+        self.canvas.set_x_segments(segments);
+        let segments = &self.canvas.x_segments;
+        if let (Some(first), Some(last)) = (segments.first(), segments.last()) {
+            self.x_range = Some(CoordinateRange::new(first.start, last.end));
+            if let Some(y_range) = self.y_range {
+                self.update_scales(self.x_range.unwrap(), y_range);
+            }
+        }
+        self
+    }
+
+    /// Break the m/z axis around regions of `spectrum` that contain no signal and are wider
+    /// than `min_gap`, restricted to the current x-axis range.
+    ///
+    /// See [`SpectrumSVG::x_segments`].
+    pub fn auto_x_segments<
+        C: CentroidLike + Default + Clone,
+        D: DeconvolutedCentroidLike + Default + Clone + MZLocated,
+    >(
+        &mut self,
+        spectrum: &MultiLayerSpectrum<C, D>,
+        min_gap: f64,
+    ) -> &mut Self {
+        if self.x_range.is_none() {
+            self.axes_from(spectrum);
+        }
+        let x_range = self.x_range.unwrap();
+        let peaks = spectrum.peaks();
+        let positions = peaks
+            .iter()
+            .filter(|p| p.intensity > 0.0 && x_range.contains(p.mz))
+            .map(|p| p.mz);
+        let segments: Vec<_> = segments_from_positions(positions, min_gap, min_gap / 4.0)
+            .into_iter()
+            .map(|s| CoordinateRange::new(s.start.max(x_range.min()), s.end.min(x_range.max())))
+            .collect();
+        self.x_segments(segments)
+    }
+
+    /// Add a secondary horizontal axis drawn according to `props`, usually on the top edge,
+    /// which maps `domain` onto the same pixels as the primary horizontal axis.
+    pub fn twin_x(&mut self, domain: CoordinateRange<f64>, props: AxisProps<f64>) -> &mut Self {
+        self.canvas.twin_x(domain);
+        self.secondary_xticks = Some(props);
+        self
+    }
+
+    /// Add a secondary vertical axis drawn according to `props`, usually on the right edge,
+    /// which maps `domain` onto the same pixels as the primary vertical axis.
+    ///
+    /// The secondary axis's [`ScaleType`] is independent of the primary axis's and may be
+    /// set through [`Canvas::secondary_y_axis`].
+    pub fn twin_y(&mut self, domain: CoordinateRange<f32>, props: AxisProps<f32>) -> &mut Self {
+        self.canvas.twin_y(domain);
+        self.secondary_yticks = Some(props);
+        self
+    }
+
+    /// Set how intensities are mapped onto the vertical axis. Series drawn after
+    /// this call are positioned using the new scale.
+    pub fn y_scale_type(&mut self, scale_type: ScaleType) -> &mut Self {
+        self.canvas.y_axis.set_scale_type(scale_type);
+        self
+    }
 
+    /// Set how the horizontal coordinate is mapped onto the horizontal axis. Series drawn
+    /// after this call are positioned using the new scale.
+    pub fn x_scale_type(&mut self, scale_type: ScaleType) -> &mut Self {
+        self.canvas.x_axis.set_scale_type(scale_type);
         self
     }
 
@@ -288,8 +398,7 @@ impl SpectrumSVG {
             Bound::Unbounded => {}
         }
 
-        self.canvas
-            .update_scales(self.x_range.clone().unwrap(), self.y_range.clone().unwrap());
+        self.update_scales(self.x_range.clone().unwrap(), self.y_range.clone().unwrap());
 
         self
     }
@@ -303,6 +412,7 @@ impl SpectrumSVG {
 
     fn add_series_description(&mut self, descr: SeriesDescription) -> String {
         let tag = descr.series_type();
+        self.series_order.push(tag.clone());
         let bucket = self.series.entry(tag).or_default();
         bucket.push(descr);
         bucket.len().to_string()
@@ -312,18 +422,12 @@ impl SpectrumSVG {
         let mzs = arrays.mzs().unwrap();
         let intensities = arrays.intensities().unwrap();
 
-        let mut series = ContinuousSeries::from_iterators(
+        let series = ContinuousSeries::from_iterators(
             mzs.iter().copied(),
             intensities.iter().copied(),
             SeriesDescription::from("profile".to_string()).with_color(self.colors.next().unwrap()),
         );
-        series.slice_x(
-            self.x_range.as_ref().unwrap().start,
-            self.x_range.as_ref().unwrap().end,
-        );
-
-        let sgroup = series.to_svg(&self.canvas);
-        self.canvas.push_layer(sgroup);
+        self.add_series(series);
     }
 
     pub fn draw_centroids<C: CentroidLike + Default + Clone + 'static>(
@@ -337,7 +441,14 @@ impl SpectrumSVG {
 
         *series.color_mut() = self.colors.next().unwrap();
 
+        let labels = self
+            .peak_labels
+            .as_ref()
+            .map(|labels| series.peak_labels(labels, &self.canvas));
         self.add_series(series);
+        if let Some(labels) = labels {
+            self.add_series(labels);
+        }
     }
 
     pub fn draw_deconvoluted_centroids<
@@ -352,6 +463,30 @@ impl SpectrumSVG {
         );
         *series.color_mut() = self.colors.next().unwrap();
         self.add_series(series);
+
+        if let Some(labels) = self.deconvoluted_labels.as_ref() {
+            let x_range = self.x_range.unwrap();
+            let scale = self.canvas.x_scale();
+            let series = labels.to_series(
+                peaks.iter().filter(|p| scale.segment_of(p.mz()).is_some()),
+                &x_range,
+            );
+            self.add_series(series);
+        }
+    }
+
+    /// Label the most intense deconvoluted peaks drawn after this call with their charge
+    /// and/or neutral mass.
+    pub fn label_deconvoluted_peaks(&mut self, labels: DeconvolutedLabels) -> &mut Self {
+        self.deconvoluted_labels = Some(labels);
+        self
+    }
+
+    /// Label the peaks selected by `labels` in centroid spectra drawn after this call with
+    /// their m/z.
+    pub fn label_peaks(&mut self, labels: PeakLabels) -> &mut Self {
+        self.peak_labels = Some(labels);
+        self
     }
 
     pub fn add_as_series(&mut self, t: &impl AsSeries<f64, f32>) {
@@ -360,13 +495,52 @@ impl SpectrumSVG {
         self.add_series(series)
     }
 
-    fn draw_series<S: PlotSeries<f64, f32>>(&mut self, mut series: S) {
-        series.slice_x(
-            self.x_range.as_ref().unwrap().start,
-            self.x_range.as_ref().unwrap().end,
+    /// Overlay the averagine isotopic pattern of each deconvoluted peak as hollow markers
+    pub fn draw_isotopic_envelopes<
+        D: DeconvolutedCentroidLike + Default + Clone + MZLocated + 'static,
+    >(
+        &mut self,
+        peaks: &MassPeakSetType<D>,
+    ) {
+        let series = IsotopicEnvelopeSeries::from_deconvoluted(
+            peaks.iter().cloned(),
+            &IsotopeModel::Averagine,
+            SeriesDescription::from("isotopic-envelope").with_color(self.colors.next().unwrap()),
         );
+        self.add_series(series);
+    }
 
-        let sgroup = series.to_svg(&self.canvas);
+    /// Match the fragments described by `annotation` against the peaks of `series`, draw the
+    /// matched peaks in the color of their ion series with their labels, and draw the peptide's
+    /// sequence ladder along the top of the canvas.
+    ///
+    /// The matches are returned for further use.
+    pub fn annotate_fragments<C: CentroidLike + Clone + 'static>(
+        &mut self,
+        series: &CentroidSeries<f64, f32, C>,
+        annotation: &FragmentAnnotation,
+    ) -> Vec<FragmentMatch> {
+        let matches = annotation.match_peaks(&series.peaks);
+        self.add_series(FragmentSeries::from_matches(
+            &matches,
+            SeriesDescription::from("fragment-annotation"),
+        ));
+        let ladder = PeptideLadder::from_matches(annotation.peptide.clone(), &matches);
+        self.canvas.push_layer(ladder.to_svg(&self.canvas));
+        matches
+    }
+
+    fn draw_series<S: PlotSeries<f64, f32>>(&mut self, mut series: S) {
+        if self.canvas.x_scale().is_segmented() {
+            series.slice_x_segments(&self.canvas.x_segments);
+        } else {
+            series.slice_x(
+                self.x_range.as_ref().unwrap().start,
+                self.x_range.as_ref().unwrap().end,
+            );
+        }
+
+        let sgroup = series.to_svg_with_labels(&self.canvas, &mut self.placed_labels);
         self.canvas.push_layer(sgroup)
     }
 
@@ -401,6 +575,59 @@ impl SpectrumSVG {
         }
     }
 
+    /// Draw every series between `start` and `end` m/z with its intensity multiplied by
+    /// `factor`, marking the range with its factor. This must be called before drawing the
+    /// series it should apply to.
+    ///
+    /// The intensity axis keeps describing the unmagnified signal.
+    pub fn magnify(&mut self, start: f64, end: f64, factor: f64) -> &mut Self {
+        self.canvas
+            .magnifications
+            .push(Magnification::new(start, end, factor));
+        self
+    }
+
+    /// Draw a magnified view of part of `spectrum` as described by `inset` over this chart,
+    /// whose axes must already be fitted.
+    ///
+    /// The inset is drawn with the same peak labels as this chart.
+    pub fn draw_inset<
+        C: CentroidLike + Default + Clone + 'static,
+        D: DeconvolutedCentroidLike + Default + Clone + MZLocated + 'static,
+    >(
+        &mut self,
+        spectrum: &MultiLayerSpectrum<C, D>,
+        inset: &Inset,
+    ) -> &mut Self {
+        let y_range = inset.y_range.unwrap_or_else(|| {
+            let max_int = spectrum
+                .peaks()
+                .iter()
+                .filter(|p| inset.x_range.contains(p.mz))
+                .fold(0.0f32, |max, p| max.max(p.intensity));
+            let max_int = if max_int > 0.0 { max_int } else { 1.0 };
+            CoordinateRange::new(max_int, 0.0)
+        });
+        let mut chart = SpectrumSVG::with_size(inset.width, inset.height);
+        chart.xticks = inset.xticks.clone();
+        chart.yticks = inset.yticks.clone();
+        chart.peak_labels = self.peak_labels.clone();
+        chart.deconvoluted_labels = self.deconvoluted_labels.clone();
+        chart.x_range = Some(inset.x_range);
+        chart.y_range = Some(y_range);
+        chart.canvas.update_scales(inset.x_range, y_range);
+        chart.draw_spectrum(spectrum);
+
+        let group = inset.to_svg(
+            &self.canvas,
+            chart.render_canvas(),
+            &chart.margins(),
+            y_range,
+        );
+        self.canvas.push_layer(group);
+        self
+    }
+
     pub fn finish(&mut self) {
         if self.finished {
             return;
@@ -408,8 +635,89 @@ impl SpectrumSVG {
         self.finished = true;
     }
 
+    /// Show `legend`, adding an entry for every series drawn before the chart is rendered.
+    /// Annotation series are left out.
+    pub fn show_legend(&mut self, legend: Legend) -> &mut Self {
+        self.legend = Some(legend);
+        self
+    }
+
+    /// The legend to draw, with its series entries filled in, if it has any entries
+    fn resolved_legend(&self) -> Option<Legend> {
+        self.legend
+            .as_ref()
+            .map(|legend| legend.clone().with_series(&self.series, &self.series_order))
+            .filter(|legend| !legend.is_empty())
+    }
+
+    pub fn title(&mut self, title: impl ToString) -> &mut Self {
+        self.heading.title = Some(title.to_string());
+        self
+    }
+
+    pub fn subtitle(&mut self, subtitle: impl ToString) -> &mut Self {
+        self.heading.subtitle = Some(subtitle.to_string());
+        self
+    }
+
+    /// Describe where `spectrum` came from in the subtitle, formatted with `header`
+    pub fn header_from<C: CentroidLike, D: DeconvolutedCentroidLike, S: SpectrumLike<C, D>>(
+        &mut self,
+        spectrum: &S,
+        header: &SpectrumHeader,
+    ) -> &mut Self {
+        self.subtitle(header.format(spectrum))
+    }
+
     pub fn render_canvas(&self) -> Group {
-        self.canvas.to_svg(&self.xticks, &self.yticks)
+        let mut group = self.canvas.to_svg_with_twins(
+            &self.xticks,
+            &self.yticks,
+            self.secondary_xticks.as_ref(),
+            self.secondary_yticks.as_ref(),
+        );
+        let axes = self.axes_margins();
+        if let Some(legend) = self.resolved_legend() {
+            group = Group::new().add(group).add(
+                Group::new()
+                    .set(
+                        "transform",
+                        format!("translate({}, {})", axes.left, axes.top),
+                    )
+                    .add(legend.to_svg(self.canvas.width as f64, self.canvas.height as f64, &axes)),
+            );
+        }
+        if !self.heading.is_empty() {
+            let center = axes.left + self.canvas.width as f64 / 2.0;
+            group = Group::new().add(self.heading.to_svg(center)).add(
+                Group::new()
+                    .set(
+                        "transform",
+                        format!("translate(0, {})", self.heading.height()),
+                    )
+                    .add(group),
+            );
+        }
+        group
+    }
+
+    fn axes_margins(&self) -> Sides {
+        self.canvas.margins(
+            &self.xticks,
+            &self.yticks,
+            self.secondary_xticks.as_ref(),
+            self.secondary_yticks.as_ref(),
+        )
+    }
+
+    /// The space around the data area needed to draw the heading, the axes and any legend
+    /// outside of them without clipping them
+    pub fn margins(&self) -> Sides {
+        let margins = self.axes_margins() + self.heading.extent();
+        match self.resolved_legend() {
+            Some(legend) => margins + legend.extent(),
+            None => margins,
+        }
     }
 
     pub fn compose_with(&mut self, canvas: impl SVGCanvas) {
@@ -417,7 +725,11 @@ impl SpectrumSVG {
     }
 
     fn make_document(&self) -> Document {
-        let mut document = Document::new();
+        let (width, height) = self.canvas.document_size(&self.margins());
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0, 0, width, height));
         if let Some(css) = self.custom_css.as_ref() {
             let style = CSSStyle::new(css.to_string());
             document.append(style);
@@ -459,19 +771,289 @@ impl SpectrumSVG {
     }
 }
 
+/// A head-to-tail plot comparing a query spectrum drawn upwards with a reference spectrum
+/// drawn downwards from a shared zero line on a common m/z axis.
+///
+/// Each half is scaled relative to its own base peak, and the intensity axis is labeled in
+/// absolute percent on both sides of the zero line.
+#[derive(Debug, Clone)]
+pub struct MirrorSpectrumSVG {
+    pub plot: SpectrumSVG,
+    /// The tolerance used to find peaks present in both spectra, if they should be highlighted
+    pub match_tolerance: Option<Tolerance>,
+    pub match_color: String,
+}
+
+impl Default for MirrorSpectrumSVG {
+    fn default() -> Self {
+        let mut plot = SpectrumSVG::default();
+        plot.yticks = plot.yticks.label("Relative Intensity");
+        Self {
+            plot,
+            match_tolerance: None,
+            match_color: "crimson".to_string(),
+        }
+    }
+}
+
+impl SVGCanvas for MirrorSpectrumSVG {
+    fn get_canvas(&self) -> &Canvas<f64, f32> {
+        &self.plot.canvas
+    }
+
+    fn make_document(&self) -> Document {
+        self.plot.make_document()
+    }
+
+    fn render_canvas(&self) -> Group {
+        self.plot.render_canvas()
+    }
+
+    fn get_canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
+        &mut self.plot.canvas
+    }
+}
+
+impl MirrorSpectrumSVG {
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self::new(Canvas::new(width, height))
+    }
+
+    pub fn new(canvas: Canvas<f64, f32>) -> Self {
+        let mut inst = Self::default();
+        inst.plot.canvas = canvas;
+        inst
+    }
+
+    /// Highlight peaks found in both spectra within `tolerance` of each other
+    pub fn match_tolerance(&mut self, tolerance: Tolerance) -> &mut Self {
+        self.match_tolerance = Some(tolerance);
+        self
+    }
+
+    /// Center the intensity axis on zero and label it in percent of `query_max`
+    fn mirror_axes(&mut self, query_max: f32) {
+        let query_max = if query_max > 0.0 { query_max } else { 1.0 };
+        let y_range = CoordinateRange::new(query_max, -query_max);
+        self.plot.y_range = Some(y_range);
+        self.plot.update_scales(self.plot.x_range.unwrap(), y_range);
+        self.plot.yticks.tick_values = Some((-4..=4).map(|i| query_max * i as f32 / 4.0).collect());
+        self.plot.yticks.tick_format = AxisTickLabelStyle::custom(move |value| {
+            format!("{:.0}%", (value / query_max as f64).abs() * 100.0)
+        });
+    }
+
+    /// Run `draw` with the intensity axis scaled so that `maximum` reaches the edge of the
+    /// canvas. When `flipped`, the layers it adds are mirrored below the zero line.
+    fn draw_half(&mut self, maximum: f32, flipped: bool, draw: impl FnOnce(&mut SpectrumSVG)) {
+        let maximum = if maximum > 0.0 { maximum } else { 1.0 };
+        let canvas = &mut self.plot.canvas;
+        let domain = canvas.y_axis.scale.domain;
+        canvas.y_axis.scale.domain = CoordinateRange::new(maximum, -maximum);
+        let start = canvas.groups.len();
+
+        draw(&mut self.plot);
+
+        let canvas = &mut self.plot.canvas;
+        canvas.y_axis.scale.domain = domain;
+        if flipped {
+            let group = canvas.groups.drain(start..).fold(
+                Group::new().set("class", "mirror-reference").set(
+                    "transform",
+                    format!("translate(0, {}) scale(1, -1)", canvas.height),
+                ),
+                |group, layer| group.add(layer),
+            );
+            canvas.push_layer(group);
+        }
+    }
+
+    fn draw_zero_line(&mut self) {
+        let canvas = &mut self.plot.canvas;
+        let y = canvas.y_axis.scale.transform(0.0);
+        let line = Line::new()
+            .set("x1", 0)
+            .set("x2", canvas.width)
+            .set("y1", y)
+            .set("y2", y)
+            .set("stroke", "black")
+            .set("stroke-width", 0.75);
+        canvas.push_layer(Group::new().set("class", "mirror-zero-line").add(line));
+    }
+
+    fn highlight_matches<
+        C1: CentroidLike + Default + Clone + 'static,
+        C2: CentroidLike + Default + Clone + 'static,
+    >(
+        &mut self,
+        query: &MZPeakSetType<C1>,
+        reference: &MZPeakSetType<C2>,
+        query_max: f32,
+        reference_max: f32,
+    ) {
+        let Some(tolerance) = self.match_tolerance else {
+            return;
+        };
+        let description =
+            SeriesDescription::new("matched-peaks".to_string(), self.match_color.clone());
+
+        let matched_query = CentroidSeries::from_iterator(
+            query
+                .iter()
+                .filter(|p| reference.has_peak(p.mz(), tolerance).is_some())
+                .cloned(),
+            description.clone(),
+        );
+        self.draw_half(query_max, false, |plot| plot.add_series(matched_query));
+
+        let matched_reference = CentroidSeries::from_iterator(
+            reference
+                .iter()
+                .filter(|p| query.has_peak(p.mz(), tolerance).is_some())
+                .cloned(),
+            description,
+        );
+        self.draw_half(reference_max, true, |plot| {
+            plot.add_series(matched_reference)
+        });
+    }
+
+    /// Draw `query` above and `reference` below the zero line, highlighting shared peaks
+    /// if [`MirrorSpectrumSVG::match_tolerance`] is set.
+    pub fn draw_peaks<
+        C1: CentroidLike + Default + Clone + 'static,
+        C2: CentroidLike + Default + Clone + 'static,
+    >(
+        &mut self,
+        query: &MZPeakSetType<C1>,
+        reference: &MZPeakSetType<C2>,
+    ) {
+        if self.plot.x_range.is_none() {
+            let (min_mz, max_mz) = query
+                .iter()
+                .map(|p| p.mz())
+                .chain(reference.iter().map(|p| p.mz()))
+                .fold((f64::infinity(), -f64::infinity()), |(min, max), mz| {
+                    (min.min(mz), max.max(mz))
+                });
+            let (min_mz, max_mz) = if min_mz.is_finite() {
+                (min_mz, max_mz)
+            } else {
+                (50.0, 2000.0)
+            };
+            self.plot.x_range = Some(CoordinateRange::new(min_mz * 0.95, max_mz * 1.05));
+        }
+        let query_max = query.iter().map(|p| p.intensity()).fold(0.0, f32::max);
+        let reference_max = reference.iter().map(|p| p.intensity()).fold(0.0, f32::max);
+        self.mirror_axes(query_max);
+
+        self.draw_half(query_max, false, |plot| plot.draw_centroids(query));
+        self.draw_half(reference_max, true, |plot| plot.draw_centroids(reference));
+        self.highlight_matches(query, reference, query_max, reference_max);
+        self.draw_zero_line();
+    }
+
+    /// Draw the signal of `query` above and `reference` below the zero line. Profile
+    /// spectra are drawn as continuous curves, and centroids are drawn for both spectra if
+    /// present.
+    pub fn draw<
+        C1: CentroidLike + Default + Clone + 'static,
+        D1: DeconvolutedCentroidLike + Default + Clone + MZLocated + 'static,
+        C2: CentroidLike + Default + Clone + 'static,
+        D2: DeconvolutedCentroidLike + Default + Clone + MZLocated + 'static,
+    >(
+        &mut self,
+        query: &MultiLayerSpectrum<C1, D1>,
+        reference: &MultiLayerSpectrum<C2, D2>,
+    ) {
+        if self.plot.x_range.is_none() {
+            self.plot.axes_from(query);
+            self.plot.axes_from(reference);
+        }
+        let query_max = query.peaks().base_peak().intensity;
+        let reference_max = reference.peaks().base_peak().intensity;
+        self.mirror_axes(query_max);
+
+        self.draw_half(query_max, false, |plot| {
+            if query.signal_continuity() == SignalContinuity::Profile {
+                plot.add_as_series(query.raw_arrays().unwrap());
+            }
+            if let Some(peaks) = query.peaks.as_ref() {
+                plot.draw_centroids(peaks);
+            }
+        });
+        self.draw_half(reference_max, true, |plot| {
+            if reference.signal_continuity() == SignalContinuity::Profile {
+                plot.add_as_series(reference.raw_arrays().unwrap());
+            }
+            if let Some(peaks) = reference.peaks.as_ref() {
+                plot.draw_centroids(peaks);
+            }
+        });
+        if let (Some(query_peaks), Some(reference_peaks)) =
+            (query.peaks.as_ref(), reference.peaks.as_ref())
+        {
+            self.highlight_matches(query_peaks, reference_peaks, query_max, reference_max);
+        }
+        self.draw_zero_line();
+    }
+
+    pub fn to_string(&self) -> String {
+        self.make_document().to_string()
+    }
+
+    pub fn write<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write(self, stream)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> io::Result<()> {
+        SVGCanvas::save(self, path)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_png(self, stream)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_png(self, path)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn write_pdf<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_pdf(self, stream)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn save_pdf<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_pdf(self, path)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FeatureSVG {
     pub canvas: Canvas<f64, f32>,
     pub colors: ColorCycle,
     pub xticks: AxisProps<f64>,
     pub yticks: AxisProps<f32>,
+    pub secondary_xticks: Option<AxisProps<f64>>,
+    pub secondary_yticks: Option<AxisProps<f32>>,
     pub x_range: Option<CoordinateRange<f64>>,
     pub y_range: Option<CoordinateRange<f32>>,
     pub finished: bool,
     pub series: HashMap<String, Vec<SeriesDescription>>,
+    /// The series type of each description added to `series`, in the order they were added
+    pub series_order: Vec<String>,
     pub custom_css: Option<String>,
+    pub legend: Option<Legend>,
+    /// The number of runs whose chromatograms have been drawn by [`FeatureSVG::draw_xics`]
+    pub xic_runs: usize,
 }
 
+/// The line styles used to tell overlaid runs apart
+const RUN_DASH_PATTERNS: &[&str] = &["", "6 3", "2 2", "8 3 2 3"];
+
 impl SVGCanvas for FeatureSVG {
     fn get_canvas(&self) -> &Canvas<f64, f32> {
         &self.canvas
@@ -482,7 +1064,7 @@ impl SVGCanvas for FeatureSVG {
     }
 
     fn render_canvas(&self) -> Group {
-        self.canvas.to_svg(&self.xticks, &self.yticks)
+        self.render_canvas()
     }
 
     fn get_canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
@@ -562,6 +1144,61 @@ impl FeatureSVG {
         self
     }
 
+    /// Add a secondary horizontal axis drawn according to `props`, usually on the top edge,
+    /// which maps `domain` onto the same pixels as the primary horizontal axis.
+    pub fn twin_x(&mut self, domain: CoordinateRange<f64>, props: AxisProps<f64>) -> &mut Self {
+        self.canvas.twin_x(domain);
+        self.secondary_xticks = Some(props);
+        self
+    }
+
+    /// Add a secondary vertical axis drawn according to `props`, usually on the right edge,
+    /// which maps `domain` onto the same pixels as the primary vertical axis.
+    ///
+    /// The secondary axis's [`ScaleType`] is independent of the primary axis's and may be
+    /// set through [`Canvas::secondary_y_axis`].
+    pub fn twin_y(&mut self, domain: CoordinateRange<f32>, props: AxisProps<f32>) -> &mut Self {
+        self.canvas.twin_y(domain);
+        self.secondary_yticks = Some(props);
+        self
+    }
+
+    /// Display the time axis in `display` units when the features' times are measured
+    /// in `source` units. The axis label is suffixed with the displayed unit.
+    pub fn time_unit(&mut self, source: TimeUnit, display: TimeUnit) -> &mut Self {
+        let precision = match self.xticks.tick_format {
+            AxisTickLabelStyle::Seconds { precision, .. }
+            | AxisTickLabelStyle::Minutes { precision, .. }
+            | AxisTickLabelStyle::Precision(precision) => precision,
+            _ => 2,
+        };
+        self.xticks.tick_format = match display {
+            TimeUnit::Seconds => AxisTickLabelStyle::Seconds { precision, source },
+            TimeUnit::Minutes => AxisTickLabelStyle::Minutes { precision, source },
+        };
+        self
+    }
+
+    /// Display the time axis as `mm:ss` when the features' times are measured in `source` units
+    pub fn time_as_minutes_seconds(&mut self, source: TimeUnit) -> &mut Self {
+        self.xticks.tick_format = AxisTickLabelStyle::MinutesSeconds { source };
+        self
+    }
+
+    /// Set how intensities are mapped onto the vertical axis. Series drawn after
+    /// this call are positioned using the new scale.
+    pub fn y_scale_type(&mut self, scale_type: ScaleType) -> &mut Self {
+        self.canvas.y_axis.set_scale_type(scale_type);
+        self
+    }
+
+    /// Set how the horizontal coordinate is mapped onto the horizontal axis. Series drawn
+    /// after this call are positioned using the new scale.
+    pub fn x_scale_type(&mut self, scale_type: ScaleType) -> &mut Self {
+        self.canvas.x_axis.set_scale_type(scale_type);
+        self
+    }
+
     pub fn xlim(&mut self, xlim: impl RangeBounds<f64>) -> &mut Self {
         let axis = self.x_range.as_mut().unwrap();
         match xlim.start_bound() {
@@ -590,42 +1227,172 @@ impl FeatureSVG {
     }
 
     pub fn render_canvas(&self) -> Group {
-        self.canvas.to_svg(&self.xticks, &self.yticks)
-    }
-
-    pub fn compose_with(&mut self, canvas: impl SVGCanvas) {
-        self.canvas_mut().push_layer(canvas.render_canvas());
+        let group = self.canvas.to_svg_with_twins(
+            &self.xticks,
+            &self.yticks,
+            self.secondary_xticks.as_ref(),
+            self.secondary_yticks.as_ref(),
+        );
+        match self.resolved_legend() {
+            Some(legend) => {
+                let axes = self.axes_margins();
+                Group::new().add(group).add(
+                    Group::new()
+                        .set(
+                            "transform",
+                            format!("translate({}, {})", axes.left, axes.top),
+                        )
+                        .add(legend.to_svg(
+                            self.canvas.width as f64,
+                            self.canvas.height as f64,
+                            &axes,
+                        )),
+                )
+            }
+            None => group,
+        }
     }
 
-    fn make_document(&self) -> Document {
-        let mut document = Document::new();
-        if let Some(css) = self.custom_css.as_ref() {
-            let style = CSSStyle::new(css.to_string());
-            document.append(style);
+    /// Show `legend`, adding an entry for every series drawn before the chart is rendered
+    /// after any entries already added by [`FeatureSVG::draw_xics`]. Annotation series are
+    /// left out.
+    pub fn show_legend(&mut self, mut legend: Legend) -> &mut Self {
+        if let Some(current) = self.legend.take() {
+            legend.entries.splice(0..0, current.entries);
+            legend.excluded.extend(current.excluded);
         }
-        document.append(self.render_canvas());
-        document
+        self.legend = Some(legend);
+        self
     }
 
-    pub fn add_series(&mut self, mut series: impl PlotSeries<f64, f32>) {
-        let descr = series.description();
-        let tag = self.add_series_description(descr.clone());
-        series.set_tag(tag);
-        self.draw_series(series);
+    /// The legend to draw, with its series entries filled in, if it has any entries
+    fn resolved_legend(&self) -> Option<Legend> {
+        self.legend
+            .as_ref()
+            .map(|legend| legend.clone().with_series(&self.series, &self.series_order))
+            .filter(|legend| !legend.is_empty())
     }
 
-    fn add_series_description(&mut self, descr: SeriesDescription) -> String {
-        let tag = descr.series_type();
-        let bucket = self.series.entry(tag).or_default();
-        bucket.push(descr);
-        bucket.len().to_string()
+    fn axes_margins(&self) -> Sides {
+        self.canvas.margins(
+            &self.xticks,
+            &self.yticks,
+            self.secondary_xticks.as_ref(),
+            self.secondary_yticks.as_ref(),
+        )
+    }
+
+    /// The space around the data area needed to draw the axes and any legend outside of them
+    /// without clipping them
+    pub fn margins(&self) -> Sides {
+        match self.resolved_legend() {
+            Some(legend) => self.axes_margins() + legend.extent(),
+            None => self.axes_margins(),
+        }
+    }
+
+    /// Draw `chromatogram` in the style chosen by its type, fitting the axes to it and
+    /// labeling the intensity axis with the quantity it measures.
+    pub fn draw_chromatogram(&mut self, chromatogram: &Chromatogram) -> &mut Self {
+        let feature = chromatogram_feature(chromatogram);
+        if feature.is_empty() {
+            return self;
+        }
+        let chromatogram_type = chromatogram.chromatogram_type();
+        let unit = chromatogram
+            .arrays
+            .get(&ArrayType::IntensityArray)
+            .map(|array| array.unit)
+            .unwrap_or(Unit::Unknown);
+        self.yticks.label = Some(chromatogram_axis_label(chromatogram_type, unit));
+        if !chromatogram_type.is_ion_current() {
+            self.yticks.tick_format = AxisTickLabelStyle::Precision(2);
+        }
+        self.axes_from(&feature);
+        self.add_as_series(chromatogram);
+        self
+    }
+
+    /// Draw the chromatograms extracted from one run as lines, fitting the axes to them.
+    ///
+    /// Each target gets its own color, shared with the same target in other runs, while each
+    /// call draws its run with a different line style. Every chromatogram is added to the
+    /// legend.
+    pub fn draw_xics(&mut self, xics: &[Xic]) -> &mut Self {
+        let dash = RUN_DASH_PATTERNS[self.xic_runs % RUN_DASH_PATTERNS.len()];
+        self.xic_runs += 1;
+        for xic in xics.iter().filter(|xic| !xic.is_empty()) {
+            self.axes_from(&xic.feature);
+        }
+        let mut colors = ColorCycle::default();
+        for xic in xics {
+            let color = colors.next().unwrap();
+            if xic.is_empty() {
+                continue;
+            }
+            let mut series = xic.as_series();
+            series.description.color = color.clone();
+            let mut entry = LegendEntry::new(xic.label(), color);
+            let tag = self.add_series_description(series.description.clone());
+            series.set_tag(tag);
+            series.slice_x(
+                self.x_range.as_ref().unwrap().start,
+                self.x_range.as_ref().unwrap().end,
+            );
+            let mut group = series.to_svg(&self.canvas);
+            if !dash.is_empty() {
+                group = group.set("stroke-dasharray", dash);
+                entry = entry.dash(dash);
+            }
+            self.canvas.push_layer(group);
+            let legend = self.legend.take().unwrap_or_default().exclude("xic");
+            self.legend.insert(legend).push(entry);
+        }
+        self
+    }
+
+    pub fn compose_with(&mut self, canvas: impl SVGCanvas) {
+        self.canvas_mut().push_layer(canvas.render_canvas());
+    }
+
+    fn make_document(&self) -> Document {
+        let (width, height) = self.canvas.document_size(&self.margins());
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0, 0, width, height));
+        if let Some(css) = self.custom_css.as_ref() {
+            let style = CSSStyle::new(css.to_string());
+            document.append(style);
+        }
+        document.append(self.render_canvas());
+        document
+    }
+
+    pub fn add_series(&mut self, mut series: impl PlotSeries<f64, f32>) {
+        let descr = series.description();
+        let tag = self.add_series_description(descr.clone());
+        series.set_tag(tag);
+        self.draw_series(series);
+    }
+
+    fn add_series_description(&mut self, descr: SeriesDescription) -> String {
+        let tag = descr.series_type();
+        self.series_order.push(tag.clone());
+        let bucket = self.series.entry(tag).or_default();
+        bucket.push(descr);
+        bucket.len().to_string()
     }
 
     fn draw_series<S: PlotSeries<f64, f32>>(&mut self, mut series: S) {
-        series.slice_x(
-            self.x_range.as_ref().unwrap().start,
-            self.x_range.as_ref().unwrap().end,
-        );
+        if self.canvas.x_scale().is_segmented() {
+            series.slice_x_segments(&self.canvas.x_segments);
+        } else {
+            series.slice_x(
+                self.x_range.as_ref().unwrap().start,
+                self.x_range.as_ref().unwrap().end,
+            );
+        }
 
         let sgroup = series.to_svg(&self.canvas);
         self.canvas.push_layer(sgroup)
@@ -677,16 +1444,1281 @@ impl Default for FeatureSVG {
             colors: Default::default(),
             xticks: AxisProps::new(AxisOrientation::Bottom)
                 .label("Time")
+                .tick_format(AxisTickLabelStyle::Minutes {
+                    precision: 2,
+                    source: TimeUnit::Minutes,
+                })
                 .id("x-axis"),
             yticks: AxisProps::new(AxisOrientation::Left)
                 .label("Intensity")
                 .tick_format(AxisTickLabelStyle::SciNot(2))
                 .id("y-axis"),
+            secondary_xticks: None,
+            secondary_yticks: None,
             x_range: Default::default(),
             y_range: Default::default(),
             finished: false,
             series: HashMap::new(),
+            series_order: Vec::new(),
+            custom_css: None,
+            legend: None,
+            xic_runs: 0,
+        }
+    }
+}
+
+/// Draw a whole run as a heatmap of intensity over retention time × m/z with a
+/// [`ColorBar`] beside it
+#[derive(Debug, Clone)]
+pub struct HeatmapSVG {
+    pub canvas: Canvas<f64, f32>,
+    pub xticks: AxisProps<f64>,
+    pub yticks: AxisProps<f32>,
+    pub x_range: Option<CoordinateRange<f64>>,
+    pub y_range: Option<CoordinateRange<f32>>,
+    pub series: HashMap<String, Vec<SeriesDescription>>,
+    pub custom_css: Option<String>,
+    /// The legend for the most recently drawn heatmap
+    pub color_bar: Option<ColorBar>,
+}
+
+impl Default for HeatmapSVG {
+    fn default() -> Self {
+        Self {
+            canvas: Canvas::new(1400, 600),
+            xticks: AxisProps::new(AxisOrientation::Bottom)
+                .label("Time")
+                .tick_format(AxisTickLabelStyle::Minutes {
+                    precision: 2,
+                    source: TimeUnit::Minutes,
+                })
+                .id("x-axis"),
+            yticks: AxisProps::new(AxisOrientation::Left)
+                .label("m/z")
+                .id("y-axis"),
+            x_range: None,
+            y_range: None,
+            series: HashMap::new(),
+            custom_css: None,
+            color_bar: None,
+        }
+    }
+}
+
+impl SVGCanvas for HeatmapSVG {
+    fn get_canvas(&self) -> &Canvas<f64, f32> {
+        &self.canvas
+    }
+
+    fn make_document(&self) -> Document {
+        self.make_document()
+    }
+
+    fn render_canvas(&self) -> Group {
+        self.render_canvas()
+    }
+
+    fn get_canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
+        &mut self.canvas
+    }
+}
+
+impl HeatmapSVG {
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self::new(Canvas::new(width, height))
+    }
+
+    pub fn new(canvas: Canvas<f64, f32>) -> Self {
+        Self {
+            canvas,
+            ..Default::default()
+        }
+    }
+
+    pub fn canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
+        &mut self.canvas
+    }
+
+    pub fn xlim(&mut self, xlim: impl RangeBounds<f64>) -> &mut Self {
+        let axis = self.x_range.as_mut().unwrap();
+        match xlim.start_bound() {
+            Bound::Included(v) => axis.start = *v,
+            Bound::Excluded(v) => axis.start = *v,
+            Bound::Unbounded => {}
+        }
+
+        match xlim.end_bound() {
+            Bound::Included(v) => axis.end = *v,
+            Bound::Excluded(v) => axis.end = *v,
+            Bound::Unbounded => {}
+        }
+
+        self.canvas
+            .update_scales(self.x_range.unwrap(), self.y_range.unwrap());
+
+        self
+    }
+
+    pub fn ylim(&mut self, ylim: impl RangeBounds<f32>) -> &mut Self {
+        let axis = self.y_range.as_mut().unwrap();
+        match ylim.start_bound() {
+            Bound::Included(v) => axis.end = *v,
+            Bound::Excluded(v) => axis.end = *v,
+            Bound::Unbounded => {}
+        }
+
+        match ylim.end_bound() {
+            Bound::Included(v) => axis.start = *v,
+            Bound::Excluded(v) => axis.start = *v,
+            Bound::Unbounded => {}
+        }
+
+        self.canvas
+            .update_scales(self.x_range.unwrap(), self.y_range.unwrap());
+
+        self
+    }
+
+    /// Draw `series`, fitting the axes to its grid if they haven't been set, and replace
+    /// the color bar with one matching it
+    pub fn draw_heatmap(&mut self, series: HeatmapSeries<f64, f32>) -> &mut Self {
+        if self.x_range.is_none() {
+            self.x_range = Some(series.x_range);
+        }
+        if self.y_range.is_none() {
+            self.y_range = Some(CoordinateRange::new(
+                series.y_range.max(),
+                series.y_range.min(),
+            ));
+        }
+        self.canvas
+            .update_scales(self.x_range.unwrap(), self.y_range.unwrap());
+        self.color_bar = Some(ColorBar::from_series(&series));
+        self.add_series(series);
+        self
+    }
+
+    /// Bin `spectra` into an `x_bins` × `y_bins` grid over retention time × m/z and draw it
+    /// with `colormap`, mapping intensities onto colors with `intensity_scale`
+    pub fn draw_spectra<C: CentroidLike, D: DeconvolutedCentroidLike, S: SpectrumLike<C, D>>(
+        &mut self,
+        spectra: impl IntoIterator<Item = S>,
+        x_bins: usize,
+        y_bins: usize,
+        colormap: Colormap,
+        intensity_scale: ScaleType,
+    ) -> &mut Self {
+        let series = HeatmapSeries::from_spectra(spectra, x_bins, y_bins, "heatmap".into())
+            .colormap(colormap)
+            .intensity_scale(intensity_scale);
+        self.draw_heatmap(series)
+    }
+
+    pub fn add_series(&mut self, mut series: impl PlotSeries<f64, f32>) {
+        let descr = series.description();
+        let tag = self.add_series_description(descr.clone());
+        series.set_tag(tag);
+        if let (Some(x_range), Some(y_range)) = (self.x_range, self.y_range) {
+            series.slice_x(x_range.start, x_range.end);
+            series.slice_y(y_range.start, y_range.end);
+        }
+        let sgroup = series.to_svg(&self.canvas);
+        self.canvas.push_layer(sgroup);
+    }
+
+    fn add_series_description(&mut self, descr: SeriesDescription) -> String {
+        let tag = descr.series_type();
+        let bucket = self.series.entry(tag).or_default();
+        bucket.push(descr);
+        bucket.len().to_string()
+    }
+
+    /// The space around the data area needed to draw the axes and color bar without
+    /// clipping them
+    pub fn margins(&self) -> Sides {
+        let mut margins = self.canvas.margins(&self.xticks, &self.yticks, None, None);
+        if let Some(color_bar) = self.color_bar.as_ref() {
+            margins.right += color_bar.extent(self.canvas.height);
+        }
+        margins
+    }
+
+    pub fn render_canvas(&self) -> Group {
+        let mut group = Group::new().add(self.canvas.to_svg(&self.xticks, &self.yticks));
+        if let Some(color_bar) = self.color_bar.as_ref() {
+            let axes = self.canvas.margins(&self.xticks, &self.yticks, None, None);
+            let x = axes.left + self.canvas.width as f64 + color_bar.gap;
+            group = group.add(
+                color_bar
+                    .to_svg(self.canvas.height)
+                    .set("transform", format!("translate({}, {})", x, axes.top)),
+            );
+        }
+        group
+    }
+
+    fn make_document(&self) -> Document {
+        let (width, height) = self.canvas.document_size(&self.margins());
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0, 0, width, height));
+        if let Some(css) = self.custom_css.as_ref() {
+            let style = CSSStyle::new(css.to_string());
+            document.append(style);
+        }
+        document.append(self.render_canvas());
+        document
+    }
+
+    pub fn to_string(&self) -> String {
+        self.make_document().to_string()
+    }
+
+    pub fn write<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write(self, stream)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> io::Result<()> {
+        SVGCanvas::save(self, path)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_png(self, stream)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_png(self, path)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn write_pdf<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_pdf(self, stream)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn save_pdf<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_pdf(self, path)
+    }
+}
+
+/// The intensity axis label for a chromatogram, naming the quantity measured by its type and the
+/// unit of its intensity array when known
+pub fn chromatogram_axis_label(chromatogram_type: ChromatogramType, unit: Unit) -> String {
+    let quantity = match chromatogram_type {
+        ChromatogramType::AbsorptionChromatogram => "Absorbance",
+        ChromatogramType::EmissionChromatogram => "Emission",
+        ChromatogramType::FlowRateChromatogram => "Flow Rate",
+        ChromatogramType::PressureChromatogram => "Pressure",
+        _ => "Intensity",
+    };
+    let unit = match unit {
+        Unit::DetectorCounts => "counts",
+        Unit::CountsPerSecond => "counts/s",
+        Unit::PercentBasePeak => "%",
+        Unit::AbsorbanceUnit => "AU",
+        Unit::Volt => "V",
+        _ => return quantity.to_string(),
+    };
+    format!("{quantity} ({unit})")
+}
+
+/// The axis label for an ion mobility dimension, chosen from its array type and unit
+pub fn ion_mobility_axis_label(array_type: &ArrayType, unit: Unit) -> String {
+    let label = match unit {
+        Unit::Millisecond => "Drift Time (ms)",
+        Unit::Second => "Drift Time (s)",
+        Unit::VoltSecondPerSquareCentimeter => "1/K0 (V·s/cm²)",
+        Unit::Volt => "Compensation Voltage (V)",
+        _ => "Ion Mobility",
+    };
+    match array_type {
+        ArrayType::MeanIonMobilityArray => format!("Mean {label}"),
+        _ => label.to_string(),
+    }
+}
+
+pub const DEFAULT_PROJECTION_SIZE: f64 = 120.0;
+pub const DEFAULT_PANEL_GAP: f64 = 10.0;
+
+/// Draw a single ion mobility frame as an m/z × ion mobility heatmap, with the spectrum
+/// summed over ion mobility above it and the mobilogram summed over m/z beside it.
+#[derive(Debug, Clone)]
+pub struct IonMobilityFrameSVG {
+    pub canvas: Canvas<f64, f32>,
+    pub spectrum_canvas: Canvas<f64, f32>,
+    pub mobilogram_canvas: Canvas<f64, f32>,
+    pub xticks: AxisProps<f64>,
+    pub yticks: AxisProps<f32>,
+    pub spectrum_xticks: AxisProps<f64>,
+    pub spectrum_yticks: AxisProps<f32>,
+    pub mobilogram_xticks: AxisProps<f64>,
+    pub mobilogram_yticks: AxisProps<f32>,
+    /// The number of cells along the m/z and ion mobility dimensions
+    pub bins: (usize, usize),
+    pub colormap: Colormap,
+    pub intensity_scale: ScaleType,
+    /// The height of the spectrum and the width of the mobilogram in pixels
+    pub projection_size: f64,
+    /// The space between the heatmap and the projections in pixels
+    pub panel_gap: f64,
+    pub projection_color: String,
+    pub custom_css: Option<String>,
+}
+
+impl Default for IonMobilityFrameSVG {
+    fn default() -> Self {
+        Self {
+            canvas: Canvas::new(1000, 500),
+            spectrum_canvas: Canvas::new(1000, DEFAULT_PROJECTION_SIZE as usize),
+            mobilogram_canvas: Canvas::new(DEFAULT_PROJECTION_SIZE as usize, 500),
+            xticks: AxisProps::new(AxisOrientation::Bottom)
+                .label("m/z")
+                .id("x-axis"),
+            yticks: AxisProps::new(AxisOrientation::Left)
+                .label("Ion Mobility")
+                .id("y-axis"),
+            spectrum_xticks: AxisProps::new(AxisOrientation::Bottom)
+                // Shared with the heatmap, which labels the axis
+                .tick_format(AxisTickLabelStyle::custom(|_| String::new()))
+                .id("spectrum-x-axis"),
+            spectrum_yticks: AxisProps::new(AxisOrientation::Left)
+                .label("Intensity")
+                .tick_format(AxisTickLabelStyle::SciNot(1))
+                .tick_count(2)
+                .id("spectrum-y-axis"),
+            mobilogram_xticks: AxisProps::new(AxisOrientation::Bottom)
+                .label("Intensity")
+                .tick_format(AxisTickLabelStyle::SciNot(1))
+                .tick_count(1)
+                .id("mobilogram-x-axis"),
+            mobilogram_yticks: AxisProps::new(AxisOrientation::Left)
+                // Shared with the heatmap, which labels the axis
+                .tick_format(AxisTickLabelStyle::custom(|_| String::new()))
+                .id("mobilogram-y-axis"),
+            bins: (500, 200),
+            colormap: Colormap::default(),
+            intensity_scale: ScaleType::log10(),
+            projection_size: DEFAULT_PROJECTION_SIZE,
+            panel_gap: DEFAULT_PANEL_GAP,
+            projection_color: "steelblue".to_string(),
+            custom_css: None,
+        }
+    }
+}
+
+impl SVGCanvas for IonMobilityFrameSVG {
+    fn get_canvas(&self) -> &Canvas<f64, f32> {
+        &self.canvas
+    }
+
+    fn make_document(&self) -> Document {
+        self.make_document()
+    }
+
+    fn render_canvas(&self) -> Group {
+        self.render_canvas()
+    }
+
+    fn get_canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
+        &mut self.canvas
+    }
+}
+
+impl IonMobilityFrameSVG {
+    /// Create a chart whose heatmap is `width` × `height` pixels
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self::new(Canvas::new(width, height))
+    }
+
+    pub fn new(canvas: Canvas<f64, f32>) -> Self {
+        Self {
+            canvas,
+            ..Default::default()
+        }
+    }
+
+    pub fn bins(&mut self, mz_bins: usize, ion_mobility_bins: usize) -> &mut Self {
+        self.bins = (mz_bins, ion_mobility_bins);
+        self
+    }
+
+    /// Draw the raw arrays of `frame`. Frames without raw arrays draw nothing.
+    pub fn draw_frame<
+        C: FeatureLike<MZ, IonMobility>,
+        D: FeatureLike<Mass, IonMobility> + KnownCharge,
+    >(
+        &mut self,
+        frame: &impl IonMobilityFrameLike<C, D>,
+    ) -> &mut Self {
+        if let Some(arrays) = frame.raw_arrays() {
+            self.draw_arrays(arrays);
+        }
+        self
+    }
+
+    /// Draw each ion mobility point's m/z and intensity arrays, labeling the ion mobility
+    /// axis from the arrays' ion mobility type and unit
+    pub fn draw_arrays(&mut self, arrays: &BinaryArrayMap3D) -> &mut Self {
+        let points =
+            arrays
+                .iter()
+                .flat_map(|(im, layer)| match (layer.mzs(), layer.intensities()) {
+                    (Ok(mzs), Ok(intensities)) => mzs
+                        .iter()
+                        .zip(intensities.iter())
+                        .map(|(mz, inten)| (*mz, im as f32, *inten))
+                        .collect(),
+                    _ => Vec::new(),
+                });
+        let series = HeatmapSeries::from_triples(
+            points,
+            self.bins.0,
+            self.bins.1,
+            "ion-mobility-frame".into(),
+        )
+        .colormap(self.colormap.clone())
+        .intensity_scale(self.intensity_scale);
+        self.yticks.set_label(ion_mobility_axis_label(
+            &arrays.ion_mobility_type,
+            arrays.ion_mobility_unit,
+        ));
+        self.draw_heatmap(series)
+    }
+
+    /// Draw `series` binned over m/z × ion mobility along with its projections, replacing
+    /// any frame drawn before
+    pub fn draw_heatmap(&mut self, series: HeatmapSeries<f64, f32>) -> &mut Self {
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        let size = self.projection_size.round() as usize;
+        let x_range = CoordinateRange::new(series.x_range.min(), series.x_range.max());
+        let y_range = CoordinateRange::new(series.y_range.max(), series.y_range.min());
+        self.canvas.groups.clear();
+        self.canvas.update_scales(x_range, y_range);
+
+        let spectrum: Vec<(f64, f32)> = series
+            .project_x()
+            .into_iter()
+            .map(|(mz, total)| (mz, total as f32))
+            .collect();
+        let spectrum_max = spectrum.iter().map(|(_, v)| *v).fold(0.0, f32::max);
+        self.spectrum_canvas = Canvas::new(width, size);
+        self.spectrum_canvas.update_scales(
+            x_range,
+            CoordinateRange::new(
+                if spectrum_max > 0.0 {
+                    spectrum_max * 1.05
+                } else {
+                    1.0
+                },
+                0.0,
+            ),
+        );
+
+        let mobilogram: Vec<(f64, f32)> = series
+            .project_y()
+            .into_iter()
+            .map(|(im, total)| (total, im))
+            .collect();
+        let mobilogram_max = mobilogram.iter().map(|(v, _)| *v).fold(0.0, f64::max);
+        self.mobilogram_canvas = Canvas::new(size, height);
+        self.mobilogram_canvas.update_scales(
+            CoordinateRange::new(
+                0.0,
+                if mobilogram_max > 0.0 {
+                    mobilogram_max * 1.05
+                } else {
+                    1.0
+                },
+            ),
+            y_range,
+        );
+
+        let description = SeriesDescription::new(
+            "projected-spectrum".to_string(),
+            self.projection_color.clone(),
+        );
+        let group = LineSeries::new(spectrum, description).to_svg(&self.spectrum_canvas);
+        self.spectrum_canvas.push_layer(group);
+        let description = SeriesDescription::new(
+            "projected-mobilogram".to_string(),
+            self.projection_color.clone(),
+        );
+        let group = LineSeries::new(mobilogram, description).to_svg(&self.mobilogram_canvas);
+        self.mobilogram_canvas.push_layer(group);
+
+        let group = series.to_svg(&self.canvas);
+        self.canvas.push_layer(group);
+        self
+    }
+
+    /// The document positions of the heatmap's, spectrum's and mobilogram's data areas,
+    /// and the size of the whole document
+    fn layout(&self) -> ([(f64, f64); 3], (f64, f64)) {
+        let main = self.canvas.margins(&self.xticks, &self.yticks, None, None);
+        let spectrum =
+            self.spectrum_canvas
+                .margins(&self.spectrum_xticks, &self.spectrum_yticks, None, None);
+        let mobilogram = self.mobilogram_canvas.margins(
+            &self.mobilogram_xticks,
+            &self.mobilogram_yticks,
+            None,
+            None,
+        );
+        let left = main.left.max(spectrum.left);
+        let spectrum_origin = (left, spectrum.top);
+        let main_top =
+            spectrum.top + self.spectrum_canvas.height as f64 + spectrum.bottom.max(self.panel_gap);
+        let main_origin = (left, main_top);
+        let mobilogram_origin = (
+            left + self.canvas.width as f64 + main.right.max(self.panel_gap),
+            main_top,
+        );
+        let width = mobilogram_origin.0 + self.mobilogram_canvas.width as f64 + mobilogram.right;
+        let height = main_top + self.canvas.height as f64 + main.bottom.max(mobilogram.bottom);
+        (
+            [main_origin, spectrum_origin, mobilogram_origin],
+            (width, height),
+        )
+    }
+
+    /// Place a rendered canvas whose own margins are `margins` so its data area starts at
+    /// `origin`
+    fn place(group: Group, margins: Sides, origin: (f64, f64)) -> Group {
+        Group::new()
+            .set(
+                "transform",
+                format!(
+                    "translate({}, {})",
+                    origin.0 - margins.left,
+                    origin.1 - margins.top
+                ),
+            )
+            .add(group)
+    }
+
+    pub fn render_canvas(&self) -> Group {
+        let ([main, spectrum, mobilogram], _) = self.layout();
+        Group::new()
+            .set("class", "ion-mobility-frame")
+            .add(Self::place(
+                self.canvas.to_svg(&self.xticks, &self.yticks),
+                self.canvas.margins(&self.xticks, &self.yticks, None, None),
+                main,
+            ))
+            .add(Self::place(
+                self.spectrum_canvas
+                    .to_svg(&self.spectrum_xticks, &self.spectrum_yticks),
+                self.spectrum_canvas.margins(
+                    &self.spectrum_xticks,
+                    &self.spectrum_yticks,
+                    None,
+                    None,
+                ),
+                spectrum,
+            ))
+            .add(Self::place(
+                self.mobilogram_canvas
+                    .to_svg(&self.mobilogram_xticks, &self.mobilogram_yticks),
+                self.mobilogram_canvas.margins(
+                    &self.mobilogram_xticks,
+                    &self.mobilogram_yticks,
+                    None,
+                    None,
+                ),
+                mobilogram,
+            ))
+    }
+
+    fn make_document(&self) -> Document {
+        let (_, (width, height)) = self.layout();
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0, 0, width, height));
+        if let Some(css) = self.custom_css.as_ref() {
+            let style = CSSStyle::new(css.to_string());
+            document.append(style);
+        }
+        document.append(self.render_canvas());
+        document
+    }
+
+    pub fn to_string(&self) -> String {
+        self.make_document().to_string()
+    }
+
+    pub fn write<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write(self, stream)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> io::Result<()> {
+        SVGCanvas::save(self, path)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_png(self, stream)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_png(self, path)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn write_pdf<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_pdf(self, stream)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn save_pdf<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_pdf(self, path)
+    }
+}
+
+/// The default upward shift in pixels between successive spectra of a [`WaterfallSVG`]
+pub const DEFAULT_WATERFALL_OFFSET: f64 = 30.0;
+/// The space between the end of a waterfall trace and its label
+const WATERFALL_LABEL_GAP: f64 = 5.0;
+/// The smallest share of a [`WaterfallSVG`]'s width and height left to the front spectrum,
+/// reducing the offsets between spectra if they would take up more
+const WATERFALL_MIN_FRONT_SHARE: f64 = 0.5;
+
+/// One spectrum of a [`WaterfallSVG`] and the label drawn beside it
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WaterfallTrace {
+    pub label: String,
+    pub points: Vec<(f64, f32)>,
+}
+
+impl WaterfallTrace {
+    pub fn new(label: impl ToString, points: Vec<(f64, f32)>) -> Self {
+        Self {
+            label: label.to_string(),
+            points,
+        }
+    }
+
+    /// Trace the profile of `spectrum`, or draw its peaks as sticks if it is centroided
+    pub fn from_spectrum<C: CentroidLike, D: DeconvolutedCentroidLike, S: SpectrumLike<C, D>>(
+        spectrum: &S,
+        label: impl ToString,
+    ) -> Self {
+        let peaks = spectrum.peaks();
+        let points = match peaks {
+            RefPeakDataLevel::RawData(_) => peaks.iter().map(|p| (p.mz, p.intensity)).collect(),
+            _ => peaks
+                .iter()
+                .flat_map(|p| [(p.mz, 0.0), (p.mz, p.intensity), (p.mz, 0.0)])
+                .collect(),
+        };
+        Self::new(label, points)
+    }
+}
+
+/// Draw many spectra stacked into a pseudo-3D waterfall. Each spectrum is shifted upwards,
+/// and optionally to the right, of the one before it and drawn behind it, with its label
+/// beside its right end. The axes measure the front spectrum.
+#[derive(Debug, Clone)]
+pub struct WaterfallSVG {
+    pub canvas: Canvas<f64, f32>,
+    pub xticks: AxisProps<f64>,
+    pub yticks: AxisProps<f32>,
+    /// The distance in pixels each spectrum is shifted to the right of the one before it,
+    /// reduced when the stack would leave the front spectrum less than half of the canvas
+    pub x_offset: f64,
+    /// The distance in pixels each spectrum is shifted above the one before it, reduced in
+    /// the same way as [`WaterfallSVG::x_offset`]
+    pub y_offset: f64,
+    /// Whether to scale every spectrum to the height of the front spectrum
+    pub normalize: bool,
+    pub stroke: String,
+    pub fill: String,
+    pub label_props: TextProps,
+    /// How [`WaterfallSVG::draw_spectra`] formats each spectrum's scan start time, which is
+    /// measured in minutes, in its label
+    pub time_format: AxisTickLabelStyle,
+    pub x_range: Option<CoordinateRange<f64>>,
+    pub y_range: Option<CoordinateRange<f32>>,
+    /// The rendered spectra, back to front
+    pub traces: Vec<Group>,
+    labels: Vec<String>,
+    pub custom_css: Option<String>,
+}
+
+impl Default for WaterfallSVG {
+    fn default() -> Self {
+        Self {
+            canvas: Canvas::new(800, 600),
+            xticks: AxisProps::new(AxisOrientation::Bottom)
+                .label("m/z")
+                .id("x-axis"),
+            yticks: AxisProps::new(AxisOrientation::Left)
+                .label("Intensity")
+                .tick_format(AxisTickLabelStyle::SciNot(1))
+                .id("y-axis"),
+            x_offset: 0.0,
+            y_offset: DEFAULT_WATERFALL_OFFSET,
+            normalize: false,
+            stroke: "black".to_string(),
+            fill: "white".to_string(),
+            label_props: TextProps {
+                text_size: 0.8,
+                horizontal_alignment: HorizontalAlignment::Start,
+                ..Default::default()
+            },
+            time_format: AxisTickLabelStyle::Minutes {
+                precision: 2,
+                source: TimeUnit::Minutes,
+            },
+            x_range: None,
+            y_range: None,
+            traces: Vec::new(),
+            labels: Vec::new(),
             custom_css: None,
         }
     }
 }
+
+impl SVGCanvas for WaterfallSVG {
+    fn get_canvas(&self) -> &Canvas<f64, f32> {
+        &self.canvas
+    }
+
+    fn make_document(&self) -> Document {
+        self.make_document()
+    }
+
+    fn render_canvas(&self) -> Group {
+        self.render_canvas()
+    }
+
+    fn get_canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
+        &mut self.canvas
+    }
+}
+
+impl WaterfallSVG {
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Self::new(Canvas::new(width, height))
+    }
+
+    pub fn new(canvas: Canvas<f64, f32>) -> Self {
+        Self {
+            canvas,
+            ..Default::default()
+        }
+    }
+
+    pub fn canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
+        &mut self.canvas
+    }
+
+    /// Set the distances in pixels between successive spectra
+    pub fn offsets(&mut self, x_offset: f64, y_offset: f64) -> &mut Self {
+        self.x_offset = x_offset;
+        self.y_offset = y_offset;
+        self
+    }
+
+    pub fn normalize(&mut self, normalize: bool) -> &mut Self {
+        self.normalize = normalize;
+        self
+    }
+
+    pub fn time_format(&mut self, time_format: AxisTickLabelStyle) -> &mut Self {
+        self.time_format = time_format;
+        self
+    }
+
+    /// Draw `spectra` front to back, labeling each with its scan start time formatted with
+    /// [`WaterfallSVG::time_format`]
+    pub fn draw_spectra<C: CentroidLike, D: DeconvolutedCentroidLike, S: SpectrumLike<C, D>>(
+        &mut self,
+        spectra: &[S],
+    ) -> &mut Self {
+        let last_time = spectra.iter().map(|s| s.start_time()).fold(0.0, f64::max);
+        let times = CoordinateRange::new(0.0, last_time);
+        let traces = spectra
+            .iter()
+            .map(|spectrum| {
+                let mut label = self.time_format.format(&spectrum.start_time(), &times);
+                if let Some(unit) = self.time_format.unit_label() {
+                    label = format!("{label} {unit}");
+                }
+                WaterfallTrace::from_spectrum(spectrum, label)
+            })
+            .collect();
+        self.draw_traces(traces)
+    }
+
+    /// Draw `traces` front to back, fitting the axes to them and replacing any traces
+    /// drawn before
+    pub fn draw_traces(&mut self, traces: Vec<WaterfallTrace>) -> &mut Self {
+        self.traces.clear();
+        self.labels.clear();
+        let n = traces.len();
+        if n == 0 {
+            return self;
+        }
+
+        let maxima: Vec<f32> = traces
+            .iter()
+            .map(|trace| trace.points.iter().map(|(_, y)| *y).fold(0.0, f32::max))
+            .collect();
+        let (x_min, x_max) = traces
+            .iter()
+            .flat_map(|trace| trace.points.iter().map(|(x, _)| *x))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| {
+                (lo.min(x), hi.max(x))
+            });
+        let (x_min, x_max) = if x_min <= x_max {
+            (x_min, x_max)
+        } else {
+            (0.0, 1.0)
+        };
+        let y_max = if self.normalize {
+            maxima[0]
+        } else {
+            maxima.iter().copied().fold(0.0, f32::max)
+        };
+        let y_max = if y_max > 0.0 { y_max } else { 1.0 };
+        self.x_range = Some(CoordinateRange::new(x_min, x_max));
+        self.y_range = Some(CoordinateRange::new(y_max, 0.0));
+        self.canvas
+            .update_scales(self.x_range.unwrap(), self.y_range.unwrap());
+
+        // The front spectrum's axes are shrunk so the spectra behind it fit on the canvas
+        let depth = (n - 1) as f64;
+        let (width, height) = (self.canvas.width as f64, self.canvas.height as f64);
+        let fit = |offset: f64, length: f64| {
+            offset.min(length * (1.0 - WATERFALL_MIN_FRONT_SHARE) / depth.max(1.0))
+        };
+        let (x_offset, y_offset) = (fit(self.x_offset, width), fit(self.y_offset, height));
+        self.canvas.x_axis.scale.range = CoordinateRange::new(0.0, width - depth * x_offset);
+        self.canvas.y_axis.scale.range =
+            CoordinateRange::new((depth * y_offset) as f32, height as f32);
+
+        for (i, trace) in traces.into_iter().enumerate().rev() {
+            let mut offset = SeriesOffset::new(i as f64 * x_offset, i as f64 * y_offset);
+            if self.normalize && maxima[i] > 0.0 {
+                offset = offset.y_scale((y_max / maxima[i]) as f64);
+            }
+            let view = self.canvas.offset_by(&offset);
+            let mut description = SeriesDescription::from("waterfall-trace");
+            description.color = self.stroke.clone();
+            description.tag = i.to_string();
+            let series = AreaSeries::new(trace.points, description).fill(self.fill.clone());
+            let label = self
+                .label_props
+                .text(trace.label.clone())
+                .set("x", view.x_axis.scale.range.max() + WATERFALL_LABEL_GAP)
+                .set("y", view.y_axis.scale.range.max())
+                .set("dy", "-0.2em");
+            self.traces.push(
+                Group::new()
+                    .set("class", "waterfall-layer")
+                    .add(series.to_svg(&view))
+                    .add(label),
+            );
+            self.labels.push(trace.label);
+        }
+        self
+    }
+
+    /// The space around the data area needed for the axes and the spectrum labels
+    pub fn margins(&self) -> Sides {
+        let mut margins = self.canvas.margins(&self.xticks, &self.yticks, None, None);
+        let metrics = default_text_metrics();
+        let font_size = self.label_props.text_size * DEFAULT_FONT_SIZE;
+        let label_width = self
+            .labels
+            .iter()
+            .map(|label| metrics.text_width(label, font_size))
+            .fold(0.0, f64::max);
+        if label_width > 0.0 {
+            margins.right += WATERFALL_LABEL_GAP + label_width;
+        }
+        margins
+    }
+
+    pub fn render_canvas(&self) -> Group {
+        let axes = self.canvas.margins(&self.xticks, &self.yticks, None, None);
+        let traces = self.traces.iter().fold(
+            Group::new().set("class", "waterfall").set(
+                "transform",
+                format!("translate({}, {})", axes.left, axes.top),
+            ),
+            |group, trace| group.add(trace.clone()),
+        );
+        Group::new()
+            .add(traces)
+            .add(self.canvas.to_svg(&self.xticks, &self.yticks))
+    }
+
+    fn make_document(&self) -> Document {
+        let (width, height) = self.canvas.document_size(&self.margins());
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0, 0, width, height));
+        if let Some(css) = self.custom_css.as_ref() {
+            let style = CSSStyle::new(css.to_string());
+            document.append(style);
+        }
+        document.append(self.render_canvas());
+        document
+    }
+
+    pub fn to_string(&self) -> String {
+        self.make_document().to_string()
+    }
+
+    pub fn write<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write(self, stream)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> io::Result<()> {
+        SVGCanvas::save(self, path)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_png(self, stream)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_png(self, path)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn write_pdf<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_pdf(self, stream)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn save_pdf<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_pdf(self, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mzpeaks::CentroidPeak;
+
+    use crate::v2::inset::InsetCorner;
+    use crate::v2::labels::LabelLayout;
+    use crate::v2::series::{AnnotationSeries, ChromatogramStyle};
+
+    #[test]
+    fn test_mirror_plot() {
+        let query: MZPeakSetType<CentroidPeak> = MZPeakSetType::new(vec![
+            CentroidPeak::new(200.0, 1000.0, 0),
+            CentroidPeak::new(300.0, 500.0, 1),
+            CentroidPeak::new(450.0, 250.0, 2),
+        ]);
+        let reference: MZPeakSetType<CentroidPeak> = MZPeakSetType::new(vec![
+            CentroidPeak::new(200.001, 40.0, 0),
+            CentroidPeak::new(350.0, 20.0, 1),
+        ]);
+
+        let mut plot = MirrorSpectrumSVG::default();
+        plot.match_tolerance(Tolerance::PPM(10.0));
+        plot.draw_peaks(&query, &reference);
+
+        let scale = &plot.plot.canvas.y_axis.scale;
+        assert_eq!(scale.transform(0.0), 300.0);
+        assert_eq!(plot.plot.yticks.tick_values.as_ref().unwrap().len(), 9);
+        assert_eq!(
+            plot.plot
+                .yticks
+                .tick_format
+                .format(&-500.0f32, &scale.domain),
+            "50%"
+        );
+
+        let text = plot.to_string();
+        assert_eq!(text.matches("class=\"mirror-reference\"").count(), 2);
+        assert_eq!(text.matches("class=\"matched-peaks\"").count(), 2);
+        assert!(text.contains("translate(0, 600) scale(1, -1)"));
+        assert!(text.contains("mirror-zero-line"));
+    }
+
+    #[test]
+    fn test_ion_mobility_frame() {
+        use mzdata::spectrum::{BinaryDataArrayType, DataArray};
+
+        let layers = [0.8, 1.0, 1.2]
+            .iter()
+            .map(|im| {
+                let mut arrays = BinaryArrayMap::new();
+                let mut mzs = DataArray::from_name_and_type(
+                    &ArrayType::MZArray,
+                    BinaryDataArrayType::Float64,
+                );
+                mzs.extend(&[400.0f64, 500.0, 600.0 * im]).unwrap();
+                let mut intensities = DataArray::from_name_and_type(
+                    &ArrayType::IntensityArray,
+                    BinaryDataArrayType::Float32,
+                );
+                intensities.extend(&[10.0f32, 100.0, 1000.0]).unwrap();
+                arrays.add(mzs);
+                arrays.add(intensities);
+                arrays
+            })
+            .collect();
+        let arrays = BinaryArrayMap3D::from_ion_mobility_dimension_and_arrays(
+            vec![0.8, 1.0, 1.2],
+            ArrayType::MeanIonMobilityArray,
+            Unit::VoltSecondPerSquareCentimeter,
+            layers,
+        );
+
+        let mut chart = IonMobilityFrameSVG::with_size(400, 200);
+        chart.bins(50, 3).draw_arrays(&arrays);
+        assert_eq!(chart.yticks.label.as_deref(), Some("Mean 1/K0 (V·s/cm²)"));
+        assert_eq!(chart.spectrum_canvas.width, 400);
+        assert_eq!(chart.mobilogram_canvas.height, 200);
+        let svg = chart.to_string();
+        assert!(svg.contains("projected-spectrum"));
+        assert!(svg.contains("projected-mobilogram"));
+        assert_eq!(svg.matches("<rect").count(), 9);
+
+        // Drawing another frame replaces the heatmap along with its projections
+        chart.draw_arrays(&arrays);
+        assert_eq!(chart.canvas.groups.len(), 1);
+        assert_eq!(chart.spectrum_canvas.groups.len(), 1);
+        assert_eq!(chart.mobilogram_canvas.groups.len(), 1);
+        assert_eq!(chart.to_string().matches("<rect").count(), 9);
+    }
+
+    #[test]
+    fn test_chromatogram() {
+        use mzdata::spectrum::{BinaryDataArrayType, ChromatogramDescription, DataArray};
+
+        let chromatogram = |chromatogram_type, unit| {
+            let mut arrays = BinaryArrayMap::new();
+            let mut times =
+                DataArray::from_name_and_type(&ArrayType::TimeArray, BinaryDataArrayType::Float64);
+            times.extend(&[1.0f64, 2.0, 3.0, 4.0]).unwrap();
+            let mut intensities = DataArray::from_name_and_type(
+                &ArrayType::IntensityArray,
+                BinaryDataArrayType::Float32,
+            );
+            intensities.extend(&[5.0f32, 50.0, 20.0, 0.0]).unwrap();
+            intensities.unit = unit;
+            arrays.add(times);
+            arrays.add(intensities);
+            let description = ChromatogramDescription {
+                chromatogram_type,
+                ..Default::default()
+            };
+            Chromatogram::new(description, arrays)
+        };
+
+        let srm = chromatogram(
+            ChromatogramType::SelectedReactionMonitoringChromatogram,
+            Unit::DetectorCounts,
+        );
+        assert_eq!(srm.as_series().style(), ChromatogramStyle::Filled);
+        let mut chart = FeatureSVG::default();
+        chart.draw_chromatogram(&srm);
+        assert_eq!(chart.yticks.label.as_deref(), Some("Intensity (counts)"));
+        assert!(chart.to_string().contains("class=\"srm\""));
+
+        let pressure = chromatogram(ChromatogramType::PressureChromatogram, Unit::Unknown);
+        assert_eq!(pressure.as_series().style(), ChromatogramStyle::Line);
+        let mut chart = FeatureSVG::default();
+        chart.draw_chromatogram(&pressure);
+        assert_eq!(chart.yticks.label.as_deref(), Some("Pressure"));
+        assert!(chart.to_string().contains("<polyline"));
+    }
+
+    #[test]
+    fn test_draw_xics() {
+        let xic = |mz: f64, run: &str, intensities: &[f32]| {
+            let mut xic = Xic::new(mz.into(), Tolerance::PPM(10.0), Some(run.to_string()));
+            for (i, inten) in intensities.iter().enumerate() {
+                xic.feature.push_raw(mz, i as f64 + 1.0, *inten);
+            }
+            xic
+        };
+        let mut chart = FeatureSVG::with_size(600, 300);
+        chart
+            .draw_xics(&[
+                xic(500.0, "a", &[10.0, 50.0]),
+                xic(600.0, "a", &[5.0, 20.0]),
+            ])
+            .draw_xics(&[xic(500.0, "b", &[30.0, 15.0]), xic(600.0, "b", &[])]);
+        assert_eq!(chart.xic_runs, 2);
+
+        // Empty chromatograms are left out, while targets keep their color across runs
+        let entries = chart.legend.as_ref().unwrap().visible_entries();
+        let labels: Vec<_> = entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["a: 500.0000", "a: 600.0000", "b: 500.0000"]);
+        assert_eq!(entries[0].color, entries[2].color);
+        assert_ne!(entries[0].color, entries[1].color);
+        let dashes: Vec<_> = entries.iter().map(|e| e.dash.as_deref()).collect();
+        assert_eq!(dashes, [None, None, Some("6 3")]);
+
+        let svg = chart.to_string();
+        assert_eq!(svg.matches("stroke-dasharray=\"6 3\"").count(), 2);
+    }
+
+    #[test]
+    fn test_waterfall() {
+        let traces = (0..3)
+            .map(|i| {
+                WaterfallTrace::new(
+                    format!("run {i}"),
+                    vec![(100.0, 0.0), (150.0, 10.0 * (i + 1) as f32), (200.0, 0.0)],
+                )
+            })
+            .collect();
+        let mut chart = WaterfallSVG::with_size(400, 300);
+        chart.offsets(10.0, 40.0).draw_traces(traces);
+        assert_eq!(chart.traces.len(), 3);
+        assert_eq!(chart.canvas.x_axis.scale.range.end, 380.0);
+        assert_eq!(chart.canvas.y_axis.scale.range.start, 80.0);
+
+        let front = chart.canvas.offset_by(&SeriesOffset::default());
+        let back = chart
+            .canvas
+            .offset_by(&SeriesOffset::new(20.0, 80.0).y_scale(0.5));
+        let (x0, y0) = front.transform(150.0, 30.0);
+        let (x1, y1) = back.transform(150.0, 30.0);
+        assert_eq!(x1 - x0, 20.0);
+        assert_eq!(
+            back.transform(150.0, 0.0).1,
+            front.transform(150.0, 0.0).1 - 80.0
+        );
+        assert_eq!(y1, 300.0 - 80.0 - (300.0 - y0) * 0.5);
+
+        // Offsets too large for the canvas are reduced to leave room for the front spectrum
+        let traces = (0..4)
+            .map(|i| WaterfallTrace::new(format!("run {i}"), vec![(100.0, 0.0), (200.0, 1.0)]))
+            .collect();
+        chart.offsets(10.0, 100.0).draw_traces(traces);
+        assert_eq!(chart.canvas.y_axis.scale.range.start, 150.0);
+        assert_eq!(chart.canvas.x_axis.scale.range.end, 370.0);
+
+        // The offset copy keeps the canvas's magnifications and broken axis
+        let mut canvas = chart.canvas.clone();
+        canvas
+            .magnifications
+            .push(Magnification::new(140.0, 160.0, 2.0));
+        canvas.set_x_segments(vec![
+            CoordinateRange::new(100.0, 140.0),
+            CoordinateRange::new(145.0, 200.0),
+        ]);
+        let view = canvas.offset_by(&SeriesOffset::new(20.0, 0.0));
+        let (x0, y0) = canvas.transform(150.0, 0.5);
+        assert_eq!(view.transform(150.0, 0.5), (x0 + 20.0, y0));
+        assert!(view.groups.is_empty());
+
+        // The back spectrum is drawn first so the spectra in front of it hide it
+        let svg = chart.to_string();
+        assert!(svg.find("run 2").unwrap() < svg.find("run 0").unwrap());
+        let axes = chart
+            .canvas
+            .margins(&chart.xticks, &chart.yticks, None, None);
+        assert!(chart.margins().right > axes.right);
+
+        // Traces without any points still get usable axes
+        chart.draw_traces(vec![WaterfallTrace::new("empty", Vec::new()); 2]);
+        assert_eq!(chart.traces.len(), 2);
+        assert!(!chart.to_string().contains("NaN"));
+
+        // Spectra are labeled with their start time in the unit of the time format
+        let mut spectrum: MultiLayerSpectrum = MultiLayerSpectrum::default();
+        spectrum
+            .description_mut()
+            .acquisition
+            .first_scan_mut()
+            .unwrap()
+            .start_time = 12.3456;
+        chart.draw_spectra(&[spectrum.clone()]);
+        assert!(chart.to_string().contains("12.35 min"));
+        chart
+            .time_format(AxisTickLabelStyle::Seconds {
+                precision: 0,
+                source: TimeUnit::Minutes,
+            })
+            .draw_spectra(&[spectrum]);
+        assert!(chart.to_string().contains("741 s"));
+    }
+
+    #[test]
+    fn test_inset() {
+        let spectrum: MultiLayerSpectrum = MultiLayerSpectrum {
+            peaks: Some(MZPeakSetType::new(vec![
+                CentroidPeak::new(300.0, 1000.0, 0),
+                CentroidPeak::new(500.0, 40.0, 1),
+                CentroidPeak::new(500.5, 20.0, 2),
+            ])),
+            ..Default::default()
+        };
+        let mut chart = SpectrumSVG::with_size(800, 400);
+        chart.x_range = Some(CoordinateRange::new(200.0, 600.0));
+        chart.y_range = Some(CoordinateRange::new(1000.0, 0.0));
+        chart
+            .canvas
+            .update_scales(chart.x_range.unwrap(), chart.y_range.unwrap());
+        chart.draw_spectrum(&spectrum);
+
+        let inset = Inset::new(499.5, 501.0).corner(InsetCorner::TopLeft);
+        chart.draw_inset(&spectrum, &inset);
+        let draw_box = inset.draw_box(
+            &chart.canvas,
+            &Sides::uniform(30.0),
+            CoordinateRange::new(40.0, 0.0),
+        );
+        assert_eq!(draw_box.origin, (38.0, 38.0));
+        assert_eq!(draw_box.transform_to_parent(499.5, 0.0), (38.0, 178.0));
+        assert_eq!(draw_box.transform_to_parent(501.0, 40.0), (278.0, 38.0));
+
+        let svg = chart.to_string();
+        assert_eq!(svg.matches("class=\"inset-connector\"").count(), 2);
+        assert!(svg.contains("inset-region"));
+        assert!(svg.contains("inset-x-axis"));
+
+        // A window without any peaks still gets a usable intensity range
+        chart.draw_inset(&spectrum, &Inset::new(460.0, 450.0));
+        let svg = chart.to_string();
+        assert_eq!(svg.matches("class=\"inset-connector\"").count(), 4);
+        assert!(!svg.contains("NaN"));
+    }
+
+    #[test]
+    fn test_placed_labels() {
+        let mut chart = SpectrumSVG::with_size(800, 400);
+        chart.x_range = Some(CoordinateRange::new(200.0, 600.0));
+        chart.y_range = Some(CoordinateRange::new(1000.0, 0.0));
+        chart.update_scales(chart.x_range.unwrap(), chart.y_range.unwrap());
+
+        let label = |text: &str| {
+            AnnotationSeries::new(
+                vec![(400.0, 500.0, text.to_string())],
+                "label".into(),
+                TextProps::default(),
+            )
+            .layout(LabelLayout::Drop)
+        };
+        chart.add_series(label("first"));
+        assert_eq!(chart.placed_labels.len(), 1);
+        // A label drawn by a later series is kept clear of the first one
+        chart.add_series(label("second"));
+        assert_eq!(chart.placed_labels.len(), 1);
+        let svg = chart.to_string();
+        assert!(svg.contains("first"));
+        assert!(!svg.contains("second"));
+
+        chart.xlim(300.0..500.0);
+        assert!(chart.placed_labels.is_empty());
+    }
+}
@@ -1,14 +1,17 @@
-use std::fmt::{Display, LowerExp};
+use std::fmt::{Debug, Display, LowerExp};
+use std::sync::Arc;
 
 use num_traits::Float;
 
 use svg::node::element::{path::Data as PathData, Group, Line, Path, Text};
 
-use crate::linear::{CoordinateRange, Scale};
+use crate::linear::{CoordinateRange, Scale, ScaleSegments, ScaleType, SegmentedScale};
 
-pub trait RenderCoordinate: Float + Display + LowerExp {}
+use super::layout::{default_text_metrics, TextMetrics};
 
-impl<T: Float + Display + LowerExp> RenderCoordinate for T {}
+pub trait RenderCoordinate: Float + Display + LowerExp + 'static {}
+
+impl<T: Float + Display + LowerExp + 'static> RenderCoordinate for T {}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AxisOrientation {
@@ -28,8 +31,8 @@ impl AxisOrientation {
     }
 }
 
-#[allow(unused)]
-#[derive(Default, Debug, Clone, Copy)]
+/// A quantity of space on each side of a rectangle, in pixels
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Sides {
     pub top: f64,
     pub right: f64,
@@ -37,7 +40,128 @@ pub struct Sides {
     pub left: f64,
 }
 
-#[allow(unused)]
+impl Sides {
+    pub fn new(top: f64, right: f64, bottom: f64, left: f64) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    pub fn uniform(value: f64) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    pub fn get(&self, side: AxisOrientation) -> f64 {
+        match side {
+            AxisOrientation::Top => self.top,
+            AxisOrientation::Right => self.right,
+            AxisOrientation::Bottom => self.bottom,
+            AxisOrientation::Left => self.left,
+        }
+    }
+
+    pub fn get_mut(&mut self, side: AxisOrientation) -> &mut f64 {
+        match side {
+            AxisOrientation::Top => &mut self.top,
+            AxisOrientation::Right => &mut self.right,
+            AxisOrientation::Bottom => &mut self.bottom,
+            AxisOrientation::Left => &mut self.left,
+        }
+    }
+
+    /// Grow `side` to be at least `value`
+    pub fn reserve(&mut self, side: AxisOrientation, value: f64) {
+        let current = self.get_mut(side);
+        *current = current.max(value);
+    }
+
+    pub fn horizontal(&self) -> f64 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> f64 {
+        self.top + self.bottom
+    }
+}
+
+impl std::ops::Add for Sides {
+    type Output = Sides;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Sides::new(
+            self.top + rhs.top,
+            self.right + rhs.right,
+            self.bottom + rhs.bottom,
+            self.left + rhs.left,
+        )
+    }
+}
+
+pub const DEFAULT_CANVAS_PADDING: f64 = 10.0;
+
+/// A shift and vertical stretch in pixels applied to a single series drawn on a [`Canvas`],
+/// used to stack series above one another
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeriesOffset {
+    /// The distance to move the series to the right
+    pub dx: f64,
+    /// The distance to move the series upwards
+    pub dy: f64,
+    /// The factor to stretch the series' height by, measured from the bottom of the canvas
+    pub y_scale: f64,
+}
+
+impl Default for SeriesOffset {
+    fn default() -> Self {
+        Self {
+            dx: 0.0,
+            dy: 0.0,
+            y_scale: 1.0,
+        }
+    }
+}
+
+impl SeriesOffset {
+    pub fn new(dx: f64, dy: f64) -> Self {
+        Self {
+            dx,
+            dy,
+            ..Default::default()
+        }
+    }
+
+    pub fn y_scale(mut self, y_scale: f64) -> Self {
+        self.y_scale = y_scale;
+        self
+    }
+}
+
+/// A range of the horizontal axis whose values are drawn stretched away from the vertical
+/// axis' zero by `factor`, so small signals there can be seen
+#[derive(Debug, Clone, Copy)]
+pub struct Magnification<X: RenderCoordinate> {
+    pub range: CoordinateRange<X>,
+    pub factor: f64,
+}
+
+impl<X: RenderCoordinate> Magnification<X> {
+    pub fn new(start: X, end: X, factor: f64) -> Self {
+        Self {
+            range: CoordinateRange::new(start, end),
+            factor,
+        }
+    }
+
+    /// The marker drawn over the magnified range, like "×10"
+    pub fn label(&self) -> String {
+        format!("×{}", self.factor)
+    }
+}
+
+/// A drawing region nested within the data area of a [`Canvas`], with its own scales
 #[derive(Debug, Clone)]
 pub struct DrawBox<X: RenderCoordinate, Y: RenderCoordinate> {
     pub width: usize,
@@ -45,12 +169,12 @@ pub struct DrawBox<X: RenderCoordinate, Y: RenderCoordinate> {
     pub x_axis: XAxis<X>,
     pub y_axis: YAxis<Y>,
     pub groups: Vec<Group>,
-    pub origin: (X, Y),
+    /// The position of the box's top left corner in the pixels of the enclosing data area
+    pub origin: (f64, f64),
 }
 
-#[allow(unused)]
 impl<X: RenderCoordinate, Y: RenderCoordinate> DrawBox<X, Y> {
-    pub fn new(width: usize, height: usize, groups: Vec<Group>, origin: (X, Y)) -> Self {
+    pub fn new(width: usize, height: usize, groups: Vec<Group>, origin: (f64, f64)) -> Self {
         let domain = CoordinateRange::new(X::zero(), X::from(width).unwrap());
         let range = domain.clone();
         let x_axis = XAxis::new(Scale::new(domain, range));
@@ -81,18 +205,24 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> DrawBox<X, Y> {
         )
     }
 
+    /// Like [`DrawBox::transform`], but in the pixels of the enclosing data area
+    pub fn transform_to_parent(&self, x: X, y: Y) -> (f64, f64) {
+        let (x, y) = self.transform(x, y);
+        (x + self.origin.0, y + self.origin.1)
+    }
+
     pub fn push_layer(&mut self, group: Group) {
         self.groups.push(group)
     }
 
     pub fn to_svg(&self) -> Group {
-        let group = self
-            .groups
-            .iter()
-            .fold(Group::new().set("class", "draw-box"), |holder, series| {
-                holder.add(series.clone())
-            });
-        group
+        self.groups.iter().fold(
+            Group::new().set("class", "draw-box").set(
+                "transform",
+                format!("translate({}, {})", self.origin.0, self.origin.1),
+            ),
+            |holder, series| holder.add(series.clone()),
+        )
     }
 }
 
@@ -102,8 +232,19 @@ pub struct Canvas<X: RenderCoordinate, Y: RenderCoordinate> {
     pub height: usize,
     pub x_axis: XAxis<X>,
     pub y_axis: YAxis<Y>,
+    /// The segments the horizontal axis is broken into, see [`Canvas::x_scale`]
+    pub x_segments: ScaleSegments<X>,
     pub groups: Vec<Group>,
     pub subplot_offset: Option<(X, Y)>,
+    /// An independently scaled horizontal axis, usually drawn on the top edge
+    pub secondary_x_axis: Option<XAxis<X>>,
+    /// An independently scaled vertical axis, usually drawn on the right edge
+    pub secondary_y_axis: Option<YAxis<Y>>,
+    /// Extra space left around the axes when laying out the document
+    pub padding: Sides,
+    /// Ranges of the horizontal axis whose values are drawn magnified by
+    /// [`Canvas::transform`]
+    pub magnifications: Vec<Magnification<X>>,
 }
 
 impl<X: RenderCoordinate, Y: RenderCoordinate> Canvas<X, Y> {
@@ -125,8 +266,13 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> Canvas<X, Y> {
             height,
             x_axis,
             y_axis,
+            x_segments: ScaleSegments::default(),
             groups: Vec::new(),
             subplot_offset: None,
+            secondary_x_axis: None,
+            secondary_y_axis: None,
+            padding: Sides::uniform(DEFAULT_CANVAS_PADDING),
+            magnifications: Vec::new(),
         }
     }
 
@@ -135,6 +281,39 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> Canvas<X, Y> {
         self.y_axis.scale.domain = y_range;
     }
 
+    /// The horizontal axis' scale, broken into [`Canvas::x_segments`] if there are any
+    pub fn x_scale(&self) -> SegmentedScale<'_, X> {
+        self.x_segments.apply(self.x_axis.scale)
+    }
+
+    /// The secondary horizontal axis' scale, or [`Canvas::x_scale`] if it has none
+    fn secondary_x_scale(&self) -> SegmentedScale<'_, X> {
+        match self.secondary_x_axis.as_ref() {
+            Some(axis) => axis.scale.into(),
+            None => self.x_scale(),
+        }
+    }
+
+    /// Break the horizontal axis into `segments`, see [`ScaleSegments::new`]. Passing an
+    /// empty list restores a contiguous axis.
+    pub fn set_x_segments(&mut self, segments: Vec<CoordinateRange<X>>) {
+        self.x_segments = ScaleSegments::new(segments);
+    }
+
+    /// Create a secondary horizontal axis spanning the same pixels as the primary
+    /// horizontal axis but mapping from `domain`.
+    pub fn twin_x(&mut self, domain: CoordinateRange<X>) -> &mut XAxis<X> {
+        let axis = XAxis::new(Scale::new(domain, self.x_axis.scale.range));
+        self.secondary_x_axis.insert(axis)
+    }
+
+    /// Create a secondary vertical axis spanning the same pixels as the primary
+    /// vertical axis but mapping from `domain`.
+    pub fn twin_y(&mut self, domain: CoordinateRange<Y>) -> &mut YAxis<Y> {
+        let axis = YAxis::new(Scale::new(domain, self.y_axis.scale.range));
+        self.secondary_y_axis.insert(axis)
+    }
+
     pub fn make_bounding_box(&self) -> PathData {
         let x_range0 = self.x_axis.scale.range.min().to_f64().unwrap() - 1.0;
         let x_range1 = self.x_axis.scale.range.max().to_f64().unwrap() + 1.0;
@@ -150,18 +329,159 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> Canvas<X, Y> {
         path
     }
 
+    /// Map a point to pixels. Points within one of [`Canvas::magnifications`] are moved away
+    /// from the vertical axis' zero by its factor.
     pub fn transform(&self, x: X, y: Y) -> (f64, f64) {
-        (
-            self.x_axis.scale.transform(x).to_f64().unwrap(),
-            self.y_axis.scale.transform(y).to_f64().unwrap(),
-        )
+        let px = self.x_scale().transform(x).to_f64().unwrap();
+        let py = self.y_axis.scale.transform(y).to_f64().unwrap();
+        match self.magnification_at(x) {
+            Some(magnification) => {
+                let baseline = self.y_axis.scale.transform(Y::zero()).to_f64().unwrap();
+                (px, baseline + (py - baseline) * magnification.factor)
+            }
+            None => (px, py),
+        }
+    }
+
+    /// The magnification covering `x`, if any
+    pub fn magnification_at(&self, x: X) -> Option<&Magnification<X>> {
+        self.magnifications.iter().find(|m| m.range.contains(x))
+    }
+
+    /// Mark each visible magnified range with dashed dividers at its ends and its factor
+    fn magnifications_svg(&self) -> Group {
+        let width = self.width as f64;
+        let text_props = TextProps {
+            text_size: 0.9,
+            ..Default::default()
+        };
+        let mut group = Group::new().set("class", "magnifications");
+        for magnification in self.magnifications.iter() {
+            let x0 = self
+                .x_scale()
+                .transform(magnification.range.start)
+                .to_f64()
+                .unwrap();
+            let x1 = self
+                .x_scale()
+                .transform(magnification.range.end)
+                .to_f64()
+                .unwrap();
+            let (x0, x1) = (x0.min(x1), x0.max(x1));
+            if x1 <= 0.0 || x0 >= width {
+                continue;
+            }
+            for x in [x0, x1] {
+                if x > 0.0 && x < width {
+                    group = group.add(
+                        Line::new()
+                            .set("class", "magnification-divider")
+                            .set("x1", x)
+                            .set("x2", x)
+                            .set("y1", 0.0)
+                            .set("y2", self.height as f64)
+                            .set("stroke", "grey")
+                            .set("stroke-dasharray", "4 3"),
+                    );
+                }
+            }
+            let center = (x0.max(0.0) + x1.min(width)) / 2.0;
+            group = group.add(
+                text_props
+                    .text(magnification.label())
+                    .set("class", "magnification-label")
+                    .set("x", center)
+                    .set("y", DEFAULT_TICK_LABEL_SIZE)
+                    .set("dy", "0.5em"),
+            );
+        }
+        group
     }
 
     pub fn push_layer(&mut self, group: Group) {
         self.groups.push(group)
     }
 
+    /// An empty copy of this canvas with the ranges of its axes moved by `offset`, so a
+    /// series drawn on it appears shifted and stretched relative to the same series drawn on
+    /// this canvas.
+    pub fn offset_by(&self, offset: &SeriesOffset) -> Self {
+        let mut canvas = Self {
+            width: self.width,
+            height: self.height,
+            x_axis: self.x_axis,
+            y_axis: self.y_axis,
+            x_segments: self.x_segments.clone(),
+            groups: Vec::new(),
+            subplot_offset: self.subplot_offset,
+            secondary_x_axis: self.secondary_x_axis,
+            secondary_y_axis: self.secondary_y_axis,
+            padding: self.padding,
+            magnifications: self.magnifications.clone(),
+        };
+
+        let dx = X::from(offset.dx).unwrap();
+        let x_range = &mut canvas.x_axis.scale.range;
+        x_range.start = x_range.start + dx;
+        x_range.end = x_range.end + dx;
+
+        let baseline = self.y_axis.scale.range.max().to_f64().unwrap();
+        let shift = |y: Y| {
+            Y::from(baseline - offset.dy + (y.to_f64().unwrap() - baseline) * offset.y_scale)
+                .unwrap()
+        };
+        let y_range = &mut canvas.y_axis.scale.range;
+        y_range.start = shift(y_range.start);
+        y_range.end = shift(y_range.end);
+        canvas
+    }
+
+    /// Compute the space needed around the data area to fit the axes, their tick labels
+    /// and their titles without clipping, plus [`Canvas::padding`].
+    pub fn margins(
+        &self,
+        x_axis_props: &AxisProps<X>,
+        y_axis_props: &AxisProps<Y>,
+        secondary_x_axis_props: Option<&AxisProps<X>>,
+        secondary_y_axis_props: Option<&AxisProps<Y>>,
+    ) -> Sides {
+        let metrics = default_text_metrics();
+        let mut extents = Sides::default();
+        x_axis_props.reserve_space(self.x_scale(), metrics, &mut extents);
+        y_axis_props.reserve_space(self.y_axis.scale, metrics, &mut extents);
+        if let Some(props) = secondary_x_axis_props {
+            props.reserve_space(self.secondary_x_scale(), metrics, &mut extents);
+        }
+        if let Some(props) = secondary_y_axis_props {
+            let axis = self.secondary_y_axis.as_ref().unwrap_or(&self.y_axis);
+            props.reserve_space(axis.scale, metrics, &mut extents);
+        }
+        self.padding + extents
+    }
+
+    /// The size of a document holding this canvas surrounded by `margins`
+    pub fn document_size(&self, margins: &Sides) -> (f64, f64) {
+        (
+            self.width as f64 + margins.horizontal(),
+            self.height as f64 + margins.vertical(),
+        )
+    }
+
     pub fn to_svg(&self, x_axis_props: &AxisProps<X>, y_axis_props: &AxisProps<Y>) -> Group {
+        self.to_svg_with_twins(x_axis_props, y_axis_props, None, None)
+    }
+
+    /// Render the canvas like [`Canvas::to_svg`], additionally drawing secondary axes.
+    ///
+    /// Each secondary axis uses [`Canvas::secondary_x_axis`] or [`Canvas::secondary_y_axis`]
+    /// if set, otherwise it re-uses the primary axis's scale.
+    pub fn to_svg_with_twins(
+        &self,
+        x_axis_props: &AxisProps<X>,
+        y_axis_props: &AxisProps<Y>,
+        secondary_x_axis_props: Option<&AxisProps<X>>,
+        secondary_y_axis_props: Option<&AxisProps<Y>>,
+    ) -> Group {
         let canvas_id = uuid::Uuid::new_v4();
         let data = self.groups.iter().fold(
             Group::new()
@@ -170,11 +490,13 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> Canvas<X, Y> {
             |holder, series| holder.add(series.clone()),
         );
 
-        let mut container_translate = format!(
-            "translate({}, {})",
-            y_axis_props.tick_spacing() * 6.0,
-            x_axis_props.tick_spacing() * 4.0
+        let margins = self.margins(
+            x_axis_props,
+            y_axis_props,
+            secondary_x_axis_props,
+            secondary_y_axis_props,
         );
+        let mut container_translate = format!("translate({}, {})", margins.left, margins.top);
 
         if let Some((x, y)) = self.subplot_offset {
             container_translate.push_str(
@@ -189,16 +511,40 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> Canvas<X, Y> {
 
         let bbox = self.make_bounding_box();
 
-        let group = Group::new()
+        // Gridlines share the tick locations of the axes they belong to and are drawn
+        // beneath the data.
+        let mut gridlines = Group::new()
+            .set("class", "canvas-gridlines")
+            .add(x_axis_props.gridlines_svg(self.x_scale(), self))
+            .add(y_axis_props.gridlines_svg(self.y_axis.scale, self));
+        if let Some(props) = secondary_x_axis_props {
+            gridlines = gridlines.add(props.gridlines_svg(self.secondary_x_scale(), self));
+        }
+        if let Some(props) = secondary_y_axis_props {
+            let axis = self.secondary_y_axis.as_ref().unwrap_or(&self.y_axis);
+            gridlines = gridlines.add(props.gridlines_svg(axis.scale, self));
+        }
+
+        let mut group = Group::new()
             .set("transform", container_translate)
             .set("class", "canvas-container")
             .set("id", format!("canvas-container-{}", canvas_id))
+            .add(gridlines)
             .add(data.set(
                 "clip-path",
                 format!("path({})", svg::node::Value::from(bbox)),
             ))
-            .add(x_axis_props.to_svg(&self.x_axis.scale, &self))
-            .add(y_axis_props.to_svg(&self.y_axis.scale, &self));
+            .add(self.magnifications_svg())
+            .add(x_axis_props.to_svg(self.x_scale(), &self))
+            .add(y_axis_props.to_svg(self.y_axis.scale, &self));
+
+        if let Some(props) = secondary_x_axis_props {
+            group = group.add(props.to_svg(self.secondary_x_scale(), self));
+        }
+        if let Some(props) = secondary_y_axis_props {
+            let axis = self.secondary_y_axis.as_ref().unwrap_or(&self.y_axis);
+            group = group.add(props.to_svg(axis.scale, self));
+        }
         group
     }
 }
@@ -217,6 +563,14 @@ impl<T: Float> XAxis<T> {
         Self { scale }
     }
 
+    pub fn scale_type(&self) -> ScaleType {
+        self.scale.scale_type
+    }
+
+    pub fn set_scale_type(&mut self, scale_type: ScaleType) {
+        self.scale.scale_type = scale_type;
+    }
+
     pub fn domain(&self) -> &CoordinateRange<T> {
         &self.scale.domain
     }
@@ -240,6 +594,14 @@ impl<T: Float> YAxis<T> {
         Self { scale }
     }
 
+    pub fn scale_type(&self) -> ScaleType {
+        self.scale.scale_type
+    }
+
+    pub fn set_scale_type(&mut self, scale_type: ScaleType) {
+        self.scale.scale_type = scale_type;
+    }
+
     pub fn domain(&self) -> &CoordinateRange<T> {
         &self.scale.domain
     }
@@ -259,7 +621,97 @@ fn translate_y<T: Float>(y: T) -> String {
     format!("translate(0, {y})")
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// A unit of time that time coordinates are measured or displayed in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Seconds,
+    #[default]
+    Minutes,
+}
+
+impl TimeUnit {
+    /// The number of seconds in one of this unit
+    pub fn seconds(&self) -> f64 {
+        match self {
+            TimeUnit::Seconds => 1.0,
+            TimeUnit::Minutes => 60.0,
+        }
+    }
+
+    /// Convert `value` from this unit to `unit`
+    pub fn convert(&self, value: f64, unit: TimeUnit) -> f64 {
+        value * self.seconds() / unit.seconds()
+    }
+
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            TimeUnit::Seconds => "s",
+            TimeUnit::Minutes => "min",
+        }
+    }
+}
+
+/// A user-supplied function for formatting tick values
+#[derive(Clone)]
+pub struct TickFormatter(pub Arc<dyn Fn(f64) -> String + Send + Sync>);
+
+impl TickFormatter {
+    pub fn new(formatter: impl Fn(f64) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(formatter))
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        (self.0)(value)
+    }
+}
+
+impl Debug for TickFormatter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TickFormatter")
+            .field(&Arc::as_ptr(&self.0))
+            .finish()
+    }
+}
+
+/// Two formatters are only equal if they are the same function object
+impl PartialEq for TickFormatter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+const SI_PREFIXES: &[(i32, &str)] = &[
+    (-12, "p"),
+    (-9, "n"),
+    (-6, "µ"),
+    (-3, "m"),
+    (0, ""),
+    (3, "k"),
+    (6, "M"),
+    (9, "G"),
+    (12, "T"),
+];
+
+/// Split `value` into a mantissa and an exponent which is a multiple of 3
+fn engineering_parts(value: f64) -> (f64, i32) {
+    if value == 0.0 || !value.is_finite() {
+        return (value, 0);
+    }
+    let exponent = (value.abs().log10() / 3.0).floor() as i32 * 3;
+    (value / 10.0f64.powi(exponent), exponent)
+}
+
+/// Format `value` with up to `precision` decimal places, dropping trailing zeros
+fn format_trimmed(value: f64, precision: usize) -> String {
+    let text = format!("{value:.precision$}");
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AxisTickLabelStyle {
     Precision(usize),
     #[allow(unused)]
@@ -268,6 +720,26 @@ pub enum AxisTickLabelStyle {
         precision: usize,
         maximum: Option<f64>,
     },
+    /// Display a time coordinate measured in `source` units in seconds
+    Seconds {
+        precision: usize,
+        source: TimeUnit,
+    },
+    /// Display a time coordinate measured in `source` units in minutes
+    Minutes {
+        precision: usize,
+        source: TimeUnit,
+    },
+    /// Display a time coordinate measured in `source` units as `mm:ss`
+    MinutesSeconds {
+        source: TimeUnit,
+    },
+    /// Display values with an SI suffix using up to this many decimal places, e.g. `1.2M` or `340k`
+    SIPrefix(usize),
+    /// Display values in scientific notation with an exponent that is a multiple of 3, e.g. `12.30e6`
+    Engineering(usize),
+    /// Display values using a user-supplied function
+    Custom(TickFormatter),
 }
 
 impl Default for AxisTickLabelStyle {
@@ -287,12 +759,70 @@ impl AxisTickLabelStyle {
                 let percent = value / maximum * 100.0;
                 format!("{1:.*}%", precision, percent)
             }
+            AxisTickLabelStyle::Seconds { precision, source } => {
+                let value = source.convert(value.to_f64().unwrap(), TimeUnit::Seconds);
+                format!("{1:.*}", precision, value)
+            }
+            AxisTickLabelStyle::Minutes { precision, source } => {
+                let value = source.convert(value.to_f64().unwrap(), TimeUnit::Minutes);
+                format!("{1:.*}", precision, value)
+            }
+            AxisTickLabelStyle::MinutesSeconds { source } => {
+                let seconds = source
+                    .convert(value.to_f64().unwrap(), TimeUnit::Seconds)
+                    .round() as i64;
+                let sign = if seconds < 0 { "-" } else { "" };
+                let seconds = seconds.abs();
+                format!("{sign}{:02}:{:02}", seconds / 60, seconds % 60)
+            }
+            AxisTickLabelStyle::SIPrefix(precision) => {
+                let (mantissa, exponent) = engineering_parts(value.to_f64().unwrap());
+                match SI_PREFIXES.iter().find(|(e, _)| *e == exponent) {
+                    Some((_, prefix)) => {
+                        format!("{}{prefix}", format_trimmed(mantissa, *precision))
+                    }
+                    None => format!("{1:.*e}", precision, value),
+                }
+            }
+            AxisTickLabelStyle::Engineering(precision) => {
+                let (mantissa, exponent) = engineering_parts(value.to_f64().unwrap());
+                format!("{mantissa:.precision$}e{exponent}")
+            }
+            AxisTickLabelStyle::Custom(formatter) => formatter.format(value.to_f64().unwrap()),
+        }
+    }
+
+    /// Create a [`AxisTickLabelStyle::Custom`] from a function
+    pub fn custom(formatter: impl Fn(f64) -> String + Send + Sync + 'static) -> Self {
+        Self::Custom(TickFormatter::new(formatter))
+    }
+
+    /// The unit the formatted values are displayed in, if any, to be appended to the axis label
+    pub fn unit_label(&self) -> Option<&'static str> {
+        match self {
+            AxisTickLabelStyle::Seconds { .. } => Some(TimeUnit::Seconds.abbreviation()),
+            AxisTickLabelStyle::Minutes { .. } => Some(TimeUnit::Minutes.abbreviation()),
+            AxisTickLabelStyle::MinutesSeconds { .. } => Some("mm:ss"),
+            _ => None,
+        }
+    }
+
+    /// The factor that converts a coordinate into the unit ticks are displayed in, so that
+    /// automatically located ticks fall on round numbers of the displayed unit.
+    pub fn display_factor(&self) -> f64 {
+        match self {
+            AxisTickLabelStyle::Seconds { source, .. } => source.convert(1.0, TimeUnit::Seconds),
+            AxisTickLabelStyle::Minutes { source, .. }
+            | AxisTickLabelStyle::MinutesSeconds { source } => {
+                source.convert(1.0, TimeUnit::Minutes)
+            }
+            _ => 1.0,
         }
     }
 }
 
 #[allow(unused)]
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct AxisLabelOptions {
     pub tick_count: usize,
     pub tick_font_size: f64,
@@ -313,10 +843,80 @@ pub struct AxisProps<T: RenderCoordinate> {
     pub tick_label_size: Option<f64>,
     pub axis_label_size: Option<f64>,
     pub visible_ticks: bool,
+    /// The desired number of ticks when `tick_values` is not set
+    pub tick_count: Option<usize>,
+    /// The minimum number of pixels between automatically placed ticks
+    pub min_tick_spacing: f64,
+    /// The number of intervals to split each major tick interval into with minor ticks.
+    /// Values less than 2 disable minor ticks.
+    pub minor_tick_subdivisions: usize,
+    pub minor_tick_size: f64,
+    /// Draw lines across the data area at each major tick
+    pub major_gridlines: Option<GridlineStyle>,
+    /// Draw lines across the data area at each minor tick
+    pub minor_gridlines: Option<GridlineStyle>,
+}
+
+/// The appearance of gridlines drawn across a [`Canvas`]'s data area
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridlineStyle {
+    pub stroke: String,
+    pub stroke_width: f64,
+    /// An SVG `stroke-dasharray` value, e.g. `"4 2"`
+    pub dash: Option<String>,
+    pub opacity: f64,
+}
+
+impl Default for GridlineStyle {
+    fn default() -> Self {
+        Self {
+            stroke: "lightgray".to_string(),
+            stroke_width: 0.75,
+            dash: None,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl GridlineStyle {
+    pub fn new(stroke: String, stroke_width: f64, dash: Option<String>, opacity: f64) -> Self {
+        Self {
+            stroke,
+            stroke_width,
+            dash,
+            opacity,
+        }
+    }
+
+    /// A fainter, dashed style suitable for minor gridlines
+    pub fn minor() -> Self {
+        Self {
+            stroke_width: 0.5,
+            dash: Some("2 2".to_string()),
+            opacity: 0.6,
+            ..Default::default()
+        }
+    }
+
+    fn apply(&self, group: Group) -> Group {
+        let group = group
+            .set("stroke", self.stroke.clone())
+            .set("stroke-width", self.stroke_width)
+            .set("stroke-opacity", self.opacity);
+        if let Some(dash) = self.dash.as_ref() {
+            group.set("stroke-dasharray", dash.clone())
+        } else {
+            group
+        }
+    }
 }
 
 pub const DEFAULT_TICK_LABEL_SIZE: f64 = 10.0;
 pub const DEFAULT_AXIS_LABEL_SIZE: f64 = 14.0;
+pub const DEFAULT_TICK_COUNT: usize = 5;
+pub const DEFAULT_MIN_TICK_SPACING: f64 = 50.0;
+/// The space between the tick labels and the axis label
+pub const AXIS_LABEL_GAP: f64 = 6.0;
 
 impl<T: RenderCoordinate> AxisProps<T> {
     pub fn new(axis_orientation: AxisOrientation) -> Self {
@@ -332,6 +932,12 @@ impl<T: RenderCoordinate> AxisProps<T> {
             tick_label_size: None,
             axis_label_size: None,
             visible_ticks: true,
+            tick_count: None,
+            min_tick_spacing: DEFAULT_MIN_TICK_SPACING,
+            minor_tick_subdivisions: 0,
+            minor_tick_size: 3.0,
+            major_gridlines: None,
+            minor_gridlines: None,
         }
     }
 
@@ -354,6 +960,85 @@ impl<T: RenderCoordinate> AxisProps<T> {
         self
     }
 
+    pub fn tick_count(mut self, tick_count: usize) -> Self {
+        self.tick_count = Some(tick_count);
+        self
+    }
+
+    pub fn minor_ticks(mut self, subdivisions: usize) -> Self {
+        self.minor_tick_subdivisions = subdivisions;
+        self
+    }
+
+    pub fn gridlines(mut self, style: GridlineStyle) -> Self {
+        self.major_gridlines = Some(style);
+        self
+    }
+
+    pub fn minor_gridlines(mut self, style: GridlineStyle) -> Self {
+        self.minor_gridlines = Some(style);
+        self
+    }
+
+    /// The number of tick intervals that fit along the axis at least `min_tick_spacing` pixels apart
+    fn max_tick_intervals(&self, scale: &SegmentedScale<T>) -> usize {
+        let length = scale.range.size().abs().to_f64().unwrap();
+        ((length / self.min_tick_spacing.max(1.0)).floor() as usize).max(1)
+    }
+
+    /// The number of ticks to request from the scale, limited by how many ticks
+    /// can fit along the axis at least `min_tick_spacing` pixels apart.
+    pub fn target_tick_count<'a>(&self, scale: impl Into<SegmentedScale<'a, T>>) -> usize {
+        let scale = scale.into();
+        self.tick_count
+            .unwrap_or(DEFAULT_TICK_COUNT)
+            .min(self.max_tick_intervals(&scale))
+            .max(1)
+    }
+
+    /// The values to place ticks at, either the explicitly provided `tick_values`
+    /// or an automatically located set of round numbers.
+    ///
+    /// Because round steps only approximate the requested count, the request is
+    /// reduced until the ticks are no closer than `min_tick_spacing`.
+    pub fn tick_locations<'a>(&self, scale: impl Into<SegmentedScale<'a, T>>) -> Vec<T> {
+        let scale = scale.into();
+        if let Some(values) = self.tick_values.as_ref() {
+            return values.clone();
+        }
+        let factor = self.tick_format.display_factor();
+        if factor != 1.0 {
+            // Locate ticks in the displayed unit and convert them back
+            let factor_t = T::from(factor).unwrap();
+            let mut display_scale = scale;
+            display_scale.scale.domain =
+                CoordinateRange::new(scale.domain.start * factor_t, scale.domain.end * factor_t);
+            let segments: Vec<_> = scale
+                .segments
+                .iter()
+                .map(|s| CoordinateRange::new(s.start * factor_t, s.end * factor_t))
+                .collect();
+            display_scale.segments = &segments;
+            return self
+                .locate_ticks(&display_scale)
+                .into_iter()
+                .map(|v| v / factor_t)
+                .collect();
+        }
+        self.locate_ticks(&scale)
+    }
+
+    fn locate_ticks(&self, scale: &SegmentedScale<T>) -> Vec<T> {
+        let limit = self.max_tick_intervals(scale) + 1;
+        let mut count = self.target_tick_count(*scale);
+        let mut ticks = scale.ticks(count);
+        while ticks.len() > limit && count > 1 {
+            count -= 1;
+            ticks = scale.ticks(count);
+        }
+        ticks
+    }
+
     pub fn transform(&self, x: T) -> String {
         match self.axis_orientation {
             AxisOrientation::Left | AxisOrientation::Right => translate_y(x),
@@ -361,13 +1046,193 @@ impl<T: RenderCoordinate> AxisProps<T> {
         }
     }
 
+    /// The values to place minor ticks at, derived from [`AxisProps::tick_locations`]
+    pub fn minor_tick_locations<'a>(&self, scale: impl Into<SegmentedScale<'a, T>>) -> Vec<T> {
+        let scale = scale.into();
+        if self.minor_tick_subdivisions < 2 {
+            return Vec::new();
+        }
+        scale.minor_ticks(&self.tick_locations(scale), self.minor_tick_subdivisions)
+    }
+
+    fn gridline_group(
+        &self,
+        values: &[T],
+        scale: &SegmentedScale<T>,
+        style: &GridlineStyle,
+        width: f64,
+        height: f64,
+    ) -> Group {
+        values
+            .iter()
+            .map(|v| {
+                let pos = scale.transform(*v).to_f64().unwrap();
+                if self.axis_orientation.is_horizontal() {
+                    Line::new()
+                        .set("x1", pos)
+                        .set("x2", pos)
+                        .set("y1", 0)
+                        .set("y2", height)
+                } else {
+                    Line::new()
+                        .set("x1", 0)
+                        .set("x2", width)
+                        .set("y1", pos)
+                        .set("y2", pos)
+                }
+            })
+            .fold(style.apply(Group::new()), |group, line| group.add(line))
+    }
+
+    /// Draw the major and minor gridlines for this axis across `canvas`'s data area
+    pub fn gridlines_svg<'a, X: RenderCoordinate, Y: RenderCoordinate>(
+        &self,
+        scale: impl Into<SegmentedScale<'a, T>>,
+        canvas: &Canvas<X, Y>,
+    ) -> Group {
+        let scale = scale.into();
+        let width = canvas.width as f64;
+        let height = canvas.height as f64;
+        let mut container = Group::new().set("class", "gridlines").set("fill", "none");
+        if let Some(style) = self.minor_gridlines.as_ref() {
+            let values = self.minor_tick_locations(scale);
+            container = container.add(
+                self.gridline_group(&values, &scale, style, width, height)
+                    .set("class", "minor-gridlines"),
+            );
+        }
+        if let Some(style) = self.major_gridlines.as_ref() {
+            let values = self.tick_locations(scale);
+            container = container.add(
+                self.gridline_group(&values, &scale, style, width, height)
+                    .set("class", "major-gridlines"),
+            );
+        }
+        container
+    }
+
     pub fn tick_spacing(&self) -> f64 {
         self.tick_size_outer + self.tick_size_inner.max(0.0) + self.tick_padding
     }
 
-    pub fn make_path_data(&self, scale: &Scale<T>) -> PathData {
+    /// The text of each tick label paired with its position along the axis in pixels
+    pub fn tick_labels<'a>(&self, scale: impl Into<SegmentedScale<'a, T>>) -> Vec<(f64, String)> {
+        let scale = scale.into();
+        self.tick_locations(scale)
+            .iter()
+            .map(|v| {
+                (
+                    scale.transform(*v).to_f64().unwrap(),
+                    self.tick_format.format(v, &scale.domain),
+                )
+            })
+            .collect()
+    }
+
+    /// How far the ticks and their labels extend away from the axis line
+    pub fn tick_extent<'a>(
+        &self,
+        scale: impl Into<SegmentedScale<'a, T>>,
+        metrics: &dyn TextMetrics,
+    ) -> f64 {
+        let scale = scale.into();
+        if !self.visible_ticks {
+            return 0.0;
+        }
+        let font_size = self.tick_label_size.unwrap_or(DEFAULT_TICK_LABEL_SIZE);
+        let labels = if self.axis_orientation.is_horizontal() {
+            metrics.text_height(font_size)
+        } else {
+            self.tick_labels(scale)
+                .iter()
+                .map(|(_, text)| metrics.text_width(text, font_size))
+                .fold(0.0, f64::max)
+        };
+        self.tick_spacing() + labels
+    }
+
+    /// How far the whole axis, including its label, extends away from the axis line
+    pub fn extent<'a>(
+        &self,
+        scale: impl Into<SegmentedScale<'a, T>>,
+        metrics: &dyn TextMetrics,
+    ) -> f64 {
+        let scale = scale.into();
+        let ticks = self.tick_extent(scale, metrics);
+        if self.label.is_some() {
+            let font_size = self.axis_label_size.unwrap_or(DEFAULT_AXIS_LABEL_SIZE);
+            ticks + AXIS_LABEL_GAP + metrics.text_height(font_size)
+        } else {
+            ticks
+        }
+    }
+
+    /// How far the tick labels spill past the start and end of the axis line
+    pub fn overflow<'a>(
+        &self,
+        scale: impl Into<SegmentedScale<'a, T>>,
+        metrics: &dyn TextMetrics,
+    ) -> (f64, f64) {
+        let scale = scale.into();
+        if !self.visible_ticks {
+            return (0.0, 0.0);
+        }
+        let font_size = self.tick_label_size.unwrap_or(DEFAULT_TICK_LABEL_SIZE);
+        let range_start = scale.range.min().to_f64().unwrap();
+        let range_end = scale.range.max().to_f64().unwrap();
+        self.tick_labels(scale)
+            .iter()
+            .fold((0.0, 0.0), |(before, after), (position, text)| {
+                let half = if self.axis_orientation.is_horizontal() {
+                    metrics.text_width(text, font_size) / 2.0
+                } else {
+                    metrics.text_height(font_size) / 2.0
+                };
+                (
+                    f64::max(before, half - (position - range_start)),
+                    f64::max(after, position + half - range_end),
+                )
+            })
+    }
+
+    /// Grow `sides` to make room for this axis along the canvas edge it is drawn on
+    pub fn reserve_space<'a>(
+        &self,
+        scale: impl Into<SegmentedScale<'a, T>>,
+        metrics: &dyn TextMetrics,
+        sides: &mut Sides,
+    ) {
+        let scale = scale.into();
+        sides.reserve(self.axis_orientation, self.extent(scale, metrics));
+        let (before, after) = self.overflow(scale, metrics);
+        if self.axis_orientation.is_horizontal() {
+            sides.reserve(AxisOrientation::Left, before);
+            sides.reserve(AxisOrientation::Right, after);
+        } else {
+            sides.reserve(AxisOrientation::Top, before);
+            sides.reserve(AxisOrientation::Bottom, after);
+        }
+    }
+
+    pub fn make_path_data<'a>(&self, scale: impl Into<SegmentedScale<'a, T>>) -> PathData {
+        let scale = scale.into();
         let range0 = scale.range.min().to_f64().unwrap() - 1.0;
         let range1 = scale.range.max().to_f64().unwrap() + 1.0;
+        if scale.is_segmented() {
+            // Draw one line for each segment of a broken axis
+            return scale
+                .segment_ranges()
+                .into_iter()
+                .fold(PathData::default(), |path, (start, end)| {
+                    let start = start.to_f64().unwrap();
+                    let end = end.to_f64().unwrap();
+                    if self.axis_orientation.is_horizontal() {
+                        path.move_to((start, 0)).line_to((end, 0))
+                    } else {
+                        path.move_to((0, start)).line_to((0, end))
+                    }
+                });
+        }
         let path = if self.axis_orientation.is_horizontal() {
             PathData::default()
                 .move_to((range0, 0))
@@ -380,28 +1245,30 @@ impl<T: RenderCoordinate> AxisProps<T> {
         path
     }
 
-    pub fn to_svg<X: RenderCoordinate, Y: RenderCoordinate>(
+    /// Draw a pair of slanted strokes (`//`) in each gap between the segments of a broken axis
+    pub fn make_break_marker_data<'a>(&self, scale: impl Into<SegmentedScale<'a, T>>) -> PathData {
+        let scale = scale.into();
+        let ranges = scale.segment_ranges();
+        ranges.windows(2).fold(PathData::default(), |path, pair| {
+            let center = (pair[0].1.to_f64().unwrap() + pair[1].0.to_f64().unwrap()) / 2.0;
+            [-2.5, 2.5].into_iter().fold(path, |path, offset| {
+                let c = center + offset;
+                if self.axis_orientation.is_horizontal() {
+                    path.move_to((c - 2.0, 4.0)).line_to((c + 2.0, -4.0))
+                } else {
+                    path.move_to((-4.0, c + 2.0)).line_to((4.0, c - 2.0))
+                }
+            })
+        })
+    }
+
+    pub fn to_svg<'a, X: RenderCoordinate, Y: RenderCoordinate>(
         &self,
-        scale: &Scale<T>,
+        scale: impl Into<SegmentedScale<'a, T>>,
         canvas: &Canvas<X, Y>,
     ) -> Group {
-        let values = self.tick_values.clone().unwrap_or_else(|| {
-            if scale.domain.start <= scale.domain.end {
-                let span = scale.domain.end - scale.domain.start;
-                let step = span.to_f64().unwrap() / 5.0;
-                (0..6)
-                    .into_iter()
-                    .map(|i| scale.domain.start + T::from(step * i as f64).unwrap())
-                    .collect()
-            } else {
-                let span = scale.domain.start - scale.domain.end;
-                let step = span.to_f64().unwrap() / 5.0;
-                (0..6)
-                    .into_iter()
-                    .map(|i| scale.domain.end + T::from(step * i as f64).unwrap())
-                    .collect()
-            }
-        });
+        let scale = scale.into();
+        let values = self.tick_locations(scale);
 
         let spacing = self.tick_spacing();
 
@@ -431,6 +1298,17 @@ impl<T: RenderCoordinate> AxisProps<T> {
             .set("d", path);
         container = container.add(path);
 
+        if scale.is_segmented() {
+            container = container.add(
+                Path::new()
+                    .set("fill", "none")
+                    .set("stroke", "black")
+                    .set("stroke-width", 0.75)
+                    .set("class", "axis-break")
+                    .set("d", self.make_break_marker_data(scale)),
+            );
+        }
+
         if self.visible_ticks {
             container = values
                 .iter()
@@ -464,6 +1342,36 @@ impl<T: RenderCoordinate> AxisProps<T> {
                     tick_container.add(label).add(line)
                 })
                 .fold(container, |container, tick| container.add(tick));
+
+            container = self
+                .minor_tick_locations(scale)
+                .into_iter()
+                .map(|v| {
+                    let range_v = scale.transform(v).to_f64().unwrap();
+                    let line = Line::new()
+                        .set("class", "minor-tick")
+                        .set("stroke", "black")
+                        .set("stroke-width", 0.5);
+                    match self.axis_orientation {
+                        AxisOrientation::Top => line
+                            .set("x1", range_v)
+                            .set("x2", range_v)
+                            .set("y2", -self.minor_tick_size),
+                        AxisOrientation::Right => line
+                            .set("y1", range_v)
+                            .set("y2", range_v)
+                            .set("x2", self.minor_tick_size),
+                        AxisOrientation::Bottom => line
+                            .set("x1", range_v)
+                            .set("x2", range_v)
+                            .set("y2", self.minor_tick_size),
+                        AxisOrientation::Left => line
+                            .set("y1", range_v)
+                            .set("y2", range_v)
+                            .set("x2", -self.minor_tick_size),
+                    }
+                })
+                .fold(container, |container, tick| container.add(tick));
         }
 
         match self.axis_orientation {
@@ -471,7 +1379,9 @@ impl<T: RenderCoordinate> AxisProps<T> {
             AxisOrientation::Bottom => {
                 container = container.set("transform", translate_y(canvas.height as f64))
             }
-            AxisOrientation::Right => todo!(),
+            AxisOrientation::Right => {
+                container = container.set("transform", translate_x(canvas.width as f64))
+            }
             AxisOrientation::Left => {}
         }
 
@@ -479,33 +1389,36 @@ impl<T: RenderCoordinate> AxisProps<T> {
             let midpoint = (scale.range.max() - scale.range.min()) / T::from(2.0).unwrap();
             let group = Group::new().set(
                 "transform",
-                if self.axis_orientation.is_horizontal() {
-                    translate_x(midpoint)
-                } else {
-                    translate_y(midpoint) + "rotate(-90)"
+                match self.axis_orientation {
+                    AxisOrientation::Top | AxisOrientation::Bottom => translate_x(midpoint),
+                    AxisOrientation::Left => translate_y(midpoint) + "rotate(-90)",
+                    AxisOrientation::Right => translate_y(midpoint) + "rotate(90)",
                 },
             );
+            // Place the label's baseline just past the tick labels. The vertical axes' labels
+            // are rotated, so a negative offset along their local y axis moves them away from
+            // the data area on either side.
+            let font_size = self.axis_label_size.unwrap_or(DEFAULT_AXIS_LABEL_SIZE);
+            let clearance = self.tick_extent(scale, default_text_metrics()) + AXIS_LABEL_GAP;
+            let offset = match self.axis_orientation {
+                AxisOrientation::Bottom => clearance + font_size * 0.8,
+                _ => -(clearance + font_size * 0.2),
+            };
+            let label = match self.tick_format.unit_label() {
+                Some(unit) => format!("{label} ({unit})"),
+                None => label.clone(),
+            };
             container = container.add(
-                group.add(match self.axis_orientation {
-                    AxisOrientation::Top => todo!(),
-                    AxisOrientation::Right => todo!(),
-                    AxisOrientation::Bottom => Text::new(label)
-                        .set("y", spacing * 2.5)
+                group.add(
+                    Text::new(label)
+                        .set("y", offset)
                         .set("fill", "black")
                         .set(
                             "font-size",
                             self.axis_label_size.unwrap_or(DEFAULT_AXIS_LABEL_SIZE),
                         )
                         .set("text-anchor", "middle"),
-                    AxisOrientation::Left => Text::new(label)
-                        .set("y", spacing * -4.0)
-                        .set("fill", "black")
-                        .set(
-                            "font-size",
-                            self.axis_label_size.unwrap_or(DEFAULT_AXIS_LABEL_SIZE),
-                        )
-                        .set("text-anchor", "middle"),
-                }),
+                ),
             );
         }
 
@@ -562,6 +1475,7 @@ impl Default for TextProps {
 
 #[cfg(test)]
 mod test {
+    use super::super::layout::ApproximateTextMetrics;
     use super::*;
 
     #[test]
@@ -597,4 +1511,202 @@ mod test {
 
         canvas.to_svg(&props, &props2);
     }
+
+    #[test]
+    fn test_tick_locations() {
+        let mut canvas: Canvas<f64, f32> = Canvas::new(600, 200);
+        canvas.update_scales(
+            CoordinateRange::new(437.83, 2011.4),
+            CoordinateRange::new(12345.0, 0.0),
+        );
+
+        let props: AxisProps<f64> = AxisProps::new(AxisOrientation::Bottom);
+        let ticks = props.tick_locations(canvas.x_scale());
+        assert_eq!(
+            ticks,
+            vec![600.0, 800.0, 1000.0, 1200.0, 1400.0, 1600.0, 1800.0, 2000.0]
+        );
+
+        // Only four ticks fit in 200 pixels at 50 pixels apart
+        let props: AxisProps<f32> = AxisProps::new(AxisOrientation::Left).tick_count(10);
+        assert_eq!(props.target_tick_count(canvas.y_axis.scale), 4);
+        let ticks = props.tick_locations(canvas.y_axis.scale);
+        assert_eq!(ticks, vec![0.0, 5000.0, 10000.0]);
+    }
+
+    #[test]
+    fn test_twin_axes() {
+        let mut canvas: Canvas<f64, f32> = Canvas::new(600, 200);
+        canvas.update_scales(
+            CoordinateRange::new(0.0, 1000.0),
+            CoordinateRange::new(10000.0, 0.0),
+        );
+        canvas
+            .twin_y(CoordinateRange::new(10000.0, 0.0))
+            .set_scale_type(ScaleType::log10());
+        canvas.twin_x(CoordinateRange::new(0.0, 60.0));
+
+        let props: AxisProps<f64> = AxisProps::new(AxisOrientation::Bottom).label("m/z");
+        let props2: AxisProps<f32> = AxisProps::new(AxisOrientation::Left).label("Intensity");
+        let twin_x: AxisProps<f64> = AxisProps::new(AxisOrientation::Top).label("Time");
+        let twin_y: AxisProps<f32> = AxisProps::new(AxisOrientation::Right).label("Absolute");
+
+        let group = canvas.to_svg_with_twins(&props, &props2, Some(&twin_x), Some(&twin_y));
+        let text = group.to_string();
+        assert!(text.contains("translate(600,0)"));
+        assert!(text.contains("rotate(90)"));
+        assert!(text.contains("Time"));
+        assert_eq!(canvas.secondary_y_axis.unwrap().scale.transform(1000.0), 50.0);
+    }
+
+    #[test]
+    fn test_gridlines() {
+        let mut canvas: Canvas<f64, f32> = Canvas::new(600, 200);
+        canvas.update_scales(
+            CoordinateRange::new(0.0, 1000.0),
+            CoordinateRange::new(10000.0, 0.0),
+        );
+
+        let props: AxisProps<f64> = AxisProps::new(AxisOrientation::Bottom)
+            .minor_ticks(2)
+            .gridlines(GridlineStyle::default())
+            .minor_gridlines(GridlineStyle::minor());
+        let props2: AxisProps<f32> = AxisProps::new(AxisOrientation::Left);
+
+        let major = props.tick_locations(canvas.x_scale());
+        let minor = props.minor_tick_locations(canvas.x_scale());
+        assert_eq!(major, vec![0.0, 200.0, 400.0, 600.0, 800.0, 1000.0]);
+        assert_eq!(minor, vec![100.0, 300.0, 500.0, 700.0, 900.0]);
+
+        let text = canvas.to_svg(&props, &props2).to_string();
+        let grid_at = text.find("major-gridlines").unwrap();
+        let data_at = text.find("data-canvas").unwrap();
+        assert!(grid_at < data_at);
+        assert_eq!(text.matches("minor-tick").count(), minor.len());
+    }
+
+    #[test]
+    fn test_time_labels() {
+        let style = AxisTickLabelStyle::Minutes {
+            precision: 1,
+            source: TimeUnit::Seconds,
+        };
+        let domain = CoordinateRange::new(0.0, 600.0);
+        assert_eq!(style.format(&90.0, &domain), "1.5");
+        assert_eq!(style.unit_label(), Some("min"));
+
+        let style = AxisTickLabelStyle::MinutesSeconds {
+            source: TimeUnit::Minutes,
+        };
+        assert_eq!(style.format(&1.5, &domain), "01:30");
+
+        let style = AxisTickLabelStyle::Seconds {
+            precision: 0,
+            source: TimeUnit::Minutes,
+        };
+        assert_eq!(style.format(&0.5, &domain), "30");
+
+        // Ticks fall on round minutes even though the data are in seconds
+        let mut canvas: Canvas<f64, f32> = Canvas::new(600, 200);
+        canvas.update_scales(
+            CoordinateRange::new(0.0, 3600.0),
+            CoordinateRange::new(100.0, 0.0),
+        );
+        let props: AxisProps<f64> = AxisProps::new(AxisOrientation::Bottom)
+            .label("Time")
+            .tick_format(AxisTickLabelStyle::Minutes {
+                precision: 0,
+                source: TimeUnit::Seconds,
+            });
+        let ticks = props.tick_locations(canvas.x_scale());
+        assert_eq!(
+            ticks,
+            vec![0.0, 600.0, 1200.0, 1800.0, 2400.0, 3000.0, 3600.0]
+        );
+
+        let props2: AxisProps<f32> = AxisProps::new(AxisOrientation::Left);
+        let text = canvas.to_svg(&props, &props2).to_string();
+        assert!(text.contains("Time (min)"));
+    }
+
+    #[test]
+    fn test_tick_label_styles() {
+        let domain = CoordinateRange::new(0.0, 1e7);
+        let si = AxisTickLabelStyle::SIPrefix(1);
+        assert_eq!(si.format(&1.23e6, &domain), "1.2M");
+        assert_eq!(si.format(&340000.0, &domain), "340k");
+        assert_eq!(si.format(&0.0, &domain), "0");
+        assert_eq!(si.format(&5.0, &domain), "5");
+
+        let eng = AxisTickLabelStyle::Engineering(2);
+        assert_eq!(eng.format(&1.23e7, &domain), "12.30e6");
+
+        let custom = AxisTickLabelStyle::custom(|v| format!("<{v}>"));
+        assert_eq!(custom.format(&2.5, &domain), "<2.5>");
+        assert_eq!(custom.clone(), custom);
+        assert!(format!("{custom:?}").starts_with("Custom(TickFormatter"));
+
+        let props: AxisProps<f64> = AxisProps::new(AxisOrientation::Left).tick_format(custom);
+        let _ = props.clone();
+    }
+
+    #[test]
+    fn test_margins() {
+        let mut canvas: Canvas<f64, f32> = Canvas::new(600, 200);
+        canvas.update_scales(
+            CoordinateRange::new(0.0, 1000.0),
+            CoordinateRange::new(1e10, 0.0),
+        );
+        let x_props: AxisProps<f64> = AxisProps::new(AxisOrientation::Bottom).label("m/z");
+        let y_props: AxisProps<f32> = AxisProps::new(AxisOrientation::Left)
+            .label("Intensity")
+            .tick_format(AxisTickLabelStyle::SciNot(2));
+
+        let metrics = ApproximateTextMetrics;
+        let y_extent = y_props.extent(canvas.y_axis.scale, &metrics);
+        assert!(y_extent > y_props.tick_spacing() + metrics.text_width("1.00e10", 10.0));
+
+        let margins = canvas.margins(&x_props, &y_props, None, None);
+        assert!(margins.left > margins.right);
+        assert!(margins.bottom > margins.top);
+        assert!(margins.top >= DEFAULT_CANVAS_PADDING);
+
+        canvas.padding = Sides::uniform(0.0);
+        let unpadded = canvas.margins(&x_props, &y_props, None, None);
+        assert_eq!(unpadded + Sides::uniform(DEFAULT_CANVAS_PADDING), margins);
+
+        let (width, height) = canvas.document_size(&unpadded);
+        assert_eq!(width, 600.0 + unpadded.horizontal());
+        assert_eq!(height, 200.0 + unpadded.vertical());
+
+        let text = canvas.to_svg(&x_props, &y_props).to_string();
+        assert!(text.contains(&format!("translate({}, {})", unpadded.left, unpadded.top)));
+    }
+
+    #[test]
+    fn test_magnification() {
+        let mut canvas: Canvas<f64, f32> = Canvas::new(1000, 200);
+        canvas.update_scales(
+            CoordinateRange::new(0.0, 1000.0),
+            CoordinateRange::new(100.0, 0.0),
+        );
+        canvas
+            .magnifications
+            .push(Magnification::new(600.0, 1200.0, 10.0));
+        assert_eq!(canvas.transform(500.0, 10.0), (500.0, 180.0));
+        assert_eq!(canvas.transform(700.0, 10.0), (700.0, 0.0));
+        assert_eq!(canvas.transform(700.0, 0.0), (700.0, 200.0));
+        assert_eq!(canvas.magnification_at(1100.0).unwrap().label(), "×10");
+
+        // Only the divider inside the data area is drawn
+        let x_props: AxisProps<f64> = AxisProps::new(AxisOrientation::Bottom);
+        let y_props: AxisProps<f32> = AxisProps::new(AxisOrientation::Left);
+        let text = canvas.to_svg(&x_props, &y_props).to_string();
+        assert_eq!(text.matches("class=\"magnification-divider\"").count(), 1);
+        assert!(text.contains("\n×10\n"));
+        assert_eq!(
+            y_props.tick_labels(canvas.y_axis.scale).last().unwrap().1,
+            "100.00"
+        );
+    }
 }
@@ -0,0 +1,524 @@
+//! Arrange several charts in a grid within a single document.
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+use svg::node::element::{Group, Style as CSSStyle};
+use svg::{Document, Node};
+
+use super::chart::{FeatureSVG, SVGCanvas, SpectrumSVG};
+use super::chart_regions::{
+    AxisProps, AxisTickLabelStyle, Canvas, HorizontalAlignment, RenderCoordinate, Sides, TextProps,
+};
+use super::layout::{default_text_metrics, DEFAULT_FONT_SIZE};
+use crate::CoordinateRange;
+
+/// The default space in pixels between adjacent panels of a [`Figure`]
+pub const DEFAULT_FIGURE_SPACING: f64 = 20.0;
+
+/// A chart placed in one cell of a [`Figure`]
+#[derive(Debug, Clone)]
+pub enum Panel {
+    Spectrum(Box<SpectrumSVG>),
+    Feature(Box<FeatureSVG>),
+}
+
+impl From<SpectrumSVG> for Panel {
+    fn from(value: SpectrumSVG) -> Self {
+        Self::Spectrum(Box::new(value))
+    }
+}
+
+impl From<FeatureSVG> for Panel {
+    fn from(value: FeatureSVG) -> Self {
+        Self::Feature(Box::new(value))
+    }
+}
+
+impl Panel {
+    pub fn canvas(&self) -> &Canvas<f64, f32> {
+        match self {
+            Self::Spectrum(chart) => &chart.canvas,
+            Self::Feature(chart) => &chart.canvas,
+        }
+    }
+
+    pub fn x_range(&self) -> Option<CoordinateRange<f64>> {
+        match self {
+            Self::Spectrum(chart) => chart.x_range,
+            Self::Feature(chart) => chart.x_range,
+        }
+    }
+
+    pub fn y_range(&self) -> Option<CoordinateRange<f32>> {
+        match self {
+            Self::Spectrum(chart) => chart.y_range,
+            Self::Feature(chart) => chart.y_range,
+        }
+    }
+
+    /// Replace whichever ranges are given, updating the canvas scales once both are set
+    fn set_ranges(
+        &mut self,
+        x_range: Option<CoordinateRange<f64>>,
+        y_range: Option<CoordinateRange<f32>>,
+    ) {
+        let (canvas, x, y) = match self {
+            Self::Spectrum(chart) => (&mut chart.canvas, &mut chart.x_range, &mut chart.y_range),
+            Self::Feature(chart) => (&mut chart.canvas, &mut chart.x_range, &mut chart.y_range),
+        };
+        assert!(
+            canvas.groups.is_empty() || !(changes(*x, x_range) || changes(*y, y_range)),
+            "A panel's axes can't be changed after series have been drawn on it"
+        );
+        if x_range.is_some() {
+            *x = x_range;
+        }
+        if y_range.is_some() {
+            *y = y_range;
+        }
+        if let (Some(x), Some(y)) = (x, y) {
+            canvas.update_scales(*x, *y);
+        }
+    }
+
+    fn ticks_mut(&mut self) -> (&mut AxisProps<f64>, &mut AxisProps<f32>) {
+        match self {
+            Self::Spectrum(chart) => (&mut chart.xticks, &mut chart.yticks),
+            Self::Feature(chart) => (&mut chart.xticks, &mut chart.yticks),
+        }
+    }
+
+    pub fn margins(&self) -> Sides {
+        match self {
+            Self::Spectrum(chart) => chart.margins(),
+            Self::Feature(chart) => chart.margins(),
+        }
+    }
+
+    pub fn render_canvas(&self) -> Group {
+        match self {
+            Self::Spectrum(chart) => chart.render_canvas(),
+            Self::Feature(chart) => chart.render_canvas(),
+        }
+    }
+}
+
+/// The smallest range covering `a` and `b`, running in the same direction as `a`
+fn union<T: RenderCoordinate>(a: CoordinateRange<T>, b: CoordinateRange<T>) -> CoordinateRange<T> {
+    let min = a.min().min(b.min());
+    let max = a.max().max(b.max());
+    if a.start <= a.end {
+        CoordinateRange::new(min, max)
+    } else {
+        CoordinateRange::new(max, min)
+    }
+}
+
+/// Whether replacing `current` with `new`, if given, changes the range
+fn changes<T: RenderCoordinate>(
+    current: Option<CoordinateRange<T>>,
+    new: Option<CoordinateRange<T>>,
+) -> bool {
+    match (current, new) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(a), Some(b)) => a.start != b.start || a.end != b.end,
+    }
+}
+
+fn hide_tick_labels<T: RenderCoordinate>(ticks: &mut AxisProps<T>) {
+    ticks.tick_format = AxisTickLabelStyle::custom(|_| String::new());
+    ticks.label = None;
+}
+
+/// The positions of a [`Figure`]'s rows and columns
+struct GridLayout {
+    /// The left edge of each column's cells and the left margin of the widest panel in it
+    columns: Vec<(f64, f64)>,
+    /// The top edge of each row's cells and the top margin of the tallest panel in it
+    rows: Vec<(f64, f64)>,
+    width: f64,
+    height: f64,
+}
+
+/// Arrange [`SpectrumSVG`] and [`FeatureSVG`] panels in rows × columns within a single
+/// document, lining up the data areas of the panels in each row and column.
+///
+/// Panels may share their horizontal or vertical scales, in which case only the panels on
+/// the bottom row or left column show tick labels for the shared axis.
+#[derive(Debug, Clone)]
+pub struct Figure {
+    pub rows: usize,
+    pub columns: usize,
+    /// The panel in each cell, in row-major order
+    pub panels: Vec<Option<Panel>>,
+    pub spacing: f64,
+    pub shared_x: bool,
+    pub shared_y: bool,
+    /// Whether to letter each panel A, B, C… in row-major order
+    pub panel_letters: bool,
+    pub letter_props: TextProps,
+    /// Layers drawn over the whole figure, in document coordinates
+    pub canvas: Canvas<f64, f32>,
+    pub custom_css: Option<String>,
+}
+
+impl SVGCanvas for Figure {
+    fn get_canvas(&self) -> &Canvas<f64, f32> {
+        &self.canvas
+    }
+
+    fn get_canvas_mut(&mut self) -> &mut Canvas<f64, f32> {
+        &mut self.canvas
+    }
+
+    fn make_document(&self) -> Document {
+        self.make_document()
+    }
+
+    fn render_canvas(&self) -> Group {
+        self.render_canvas()
+    }
+}
+
+impl Figure {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            columns,
+            panels: vec![None; rows * columns],
+            spacing: DEFAULT_FIGURE_SPACING,
+            shared_x: false,
+            shared_y: false,
+            panel_letters: false,
+            letter_props: TextProps {
+                text_size: 1.2,
+                horizontal_alignment: HorizontalAlignment::Start,
+                ..Default::default()
+            },
+            canvas: Canvas::new(0, 0),
+            custom_css: None,
+        }
+    }
+
+    fn index(&self, row: usize, column: usize) -> usize {
+        assert!(
+            row < self.rows && column < self.columns,
+            "Cell ({row}, {column}) is outside of a {} × {} figure",
+            self.rows,
+            self.columns
+        );
+        row * self.columns + column
+    }
+
+    pub fn set_panel(&mut self, row: usize, column: usize, panel: impl Into<Panel>) -> &mut Self {
+        let i = self.index(row, column);
+        self.panels[i] = Some(panel.into());
+        self
+    }
+
+    pub fn panel(&self, row: usize, column: usize) -> Option<&Panel> {
+        self.panels[self.index(row, column)].as_ref()
+    }
+
+    pub fn panel_mut(&mut self, row: usize, column: usize) -> Option<&mut Panel> {
+        let i = self.index(row, column);
+        self.panels[i].as_mut()
+    }
+
+    pub fn spectrum_mut(&mut self, row: usize, column: usize) -> Option<&mut SpectrumSVG> {
+        match self.panel_mut(row, column) {
+            Some(Panel::Spectrum(chart)) => Some(chart.as_mut()),
+            _ => None,
+        }
+    }
+
+    pub fn feature_mut(&mut self, row: usize, column: usize) -> Option<&mut FeatureSVG> {
+        match self.panel_mut(row, column) {
+            Some(Panel::Feature(chart)) => Some(chart.as_mut()),
+            _ => None,
+        }
+    }
+
+    pub fn spacing(&mut self, spacing: f64) -> &mut Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn panel_letters(&mut self, panel_letters: bool) -> &mut Self {
+        self.panel_letters = panel_letters;
+        self
+    }
+
+    /// Give every panel the horizontal range covering all of their horizontal ranges.
+    ///
+    /// Series are positioned when they are drawn, so this must be called after fitting each
+    /// panel's axes and before drawing any series. Panics if a panel whose range would change
+    /// already has series drawn on it.
+    pub fn share_x(&mut self) -> &mut Self {
+        self.shared_x = true;
+        let shared = self
+            .panels
+            .iter()
+            .flatten()
+            .filter_map(Panel::x_range)
+            .reduce(union);
+        for panel in self.panels.iter_mut().flatten() {
+            panel.set_ranges(shared, None);
+        }
+        self
+    }
+
+    /// Give every panel the vertical range covering all of their vertical ranges.
+    ///
+    /// Series are positioned when they are drawn, so this must be called after fitting each
+    /// panel's axes and before drawing any series. Panics if a panel whose range would change
+    /// already has series drawn on it.
+    pub fn share_y(&mut self) -> &mut Self {
+        self.shared_y = true;
+        let shared = self
+            .panels
+            .iter()
+            .flatten()
+            .filter_map(Panel::y_range)
+            .reduce(union);
+        for panel in self.panels.iter_mut().flatten() {
+            panel.set_ranges(None, shared);
+        }
+        self
+    }
+
+    /// The panels as they are drawn, without the tick labels duplicated by shared axes
+    fn rendered_panels(&self) -> Vec<Option<Panel>> {
+        let mut panels = self.panels.clone();
+        for (i, panel) in panels.iter_mut().enumerate() {
+            let Some(panel) = panel.as_mut() else {
+                continue;
+            };
+            let (row, column) = (i / self.columns, i % self.columns);
+            let (xticks, yticks) = panel.ticks_mut();
+            if self.shared_x && row + 1 < self.rows {
+                hide_tick_labels(xticks);
+            }
+            if self.shared_y && column > 0 {
+                hide_tick_labels(yticks);
+            }
+        }
+        panels
+    }
+
+    fn letter_height(&self) -> f64 {
+        if self.panel_letters {
+            default_text_metrics().text_height(self.letter_props.text_size * DEFAULT_FONT_SIZE)
+        } else {
+            0.0
+        }
+    }
+
+    fn layout(&self, panels: &[Option<Panel>]) -> GridLayout {
+        let mut lefts = vec![0.0f64; self.columns];
+        let mut widths = vec![0.0f64; self.columns];
+        let mut rights = vec![0.0f64; self.columns];
+        let mut tops = vec![self.letter_height(); self.rows];
+        let mut heights = vec![0.0f64; self.rows];
+        let mut bottoms = vec![0.0f64; self.rows];
+        for (i, panel) in panels.iter().enumerate() {
+            let Some(panel) = panel.as_ref() else {
+                continue;
+            };
+            let (row, column) = (i / self.columns, i % self.columns);
+            let margins = panel.margins();
+            let canvas = panel.canvas();
+            lefts[column] = lefts[column].max(margins.left);
+            widths[column] = widths[column].max(canvas.width as f64);
+            rights[column] = rights[column].max(margins.right);
+            tops[row] = tops[row].max(margins.top);
+            heights[row] = heights[row].max(canvas.height as f64);
+            bottoms[row] = bottoms[row].max(margins.bottom);
+        }
+
+        let place = |starts: &[f64], sizes: &[f64], ends: &[f64]| {
+            let mut offset = 0.0;
+            let mut cells = Vec::with_capacity(starts.len());
+            for ((start, size), end) in starts.iter().zip(sizes).zip(ends) {
+                cells.push((offset, *start));
+                offset += start + size + end + self.spacing;
+            }
+            (cells, (offset - self.spacing).max(0.0))
+        };
+        let (columns, width) = place(&lefts, &widths, &rights);
+        let (rows, height) = place(&tops, &heights, &bottoms);
+        GridLayout {
+            columns,
+            rows,
+            width,
+            height,
+        }
+    }
+
+    /// The width and height of the whole figure
+    pub fn size(&self) -> (f64, f64) {
+        let layout = self.layout(&self.rendered_panels());
+        (layout.width, layout.height)
+    }
+
+    pub fn render_canvas(&self) -> Group {
+        let panels = self.rendered_panels();
+        let layout = self.layout(&panels);
+        let mut group = Group::new().set("class", "figure");
+        let mut letters = ('A'..='Z').cycle();
+        for (i, panel) in panels.iter().enumerate() {
+            let Some(panel) = panel.as_ref() else {
+                continue;
+            };
+            let (cell_x, left) = layout.columns[i % self.columns];
+            let (cell_y, top) = layout.rows[i / self.columns];
+            let margins = panel.margins();
+            let x = cell_x + left - margins.left;
+            let y = cell_y + top - margins.top;
+            group = group.add(
+                Group::new()
+                    .set("class", "figure-panel")
+                    .set("transform", format!("translate({x}, {y})"))
+                    .add(panel.render_canvas()),
+            );
+            if self.panel_letters {
+                let letter = self
+                    .letter_props
+                    .text(letters.next().unwrap().to_string())
+                    .set("x", cell_x)
+                    .set("y", cell_y)
+                    .set("dy", "1em")
+                    .set("font-weight", "bold")
+                    .set("class", "panel-letter");
+                group = group.add(letter);
+            }
+        }
+        self.canvas
+            .groups
+            .iter()
+            .fold(group, |group, layer| group.add(layer.clone()))
+    }
+
+    fn make_document(&self) -> Document {
+        let (width, height) = self.size();
+        let mut document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", (0, 0, width, height));
+        if let Some(css) = self.custom_css.as_ref() {
+            let style = CSSStyle::new(css.to_string());
+            document.append(style);
+        }
+        document.append(self.render_canvas());
+        document
+    }
+
+    pub fn to_string(&self) -> String {
+        self.make_document().to_string()
+    }
+
+    pub fn write<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write(self, stream)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> io::Result<()> {
+        SVGCanvas::save(self, path)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_png(self, stream)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_png(self, path)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn write_pdf<W: Write>(&self, stream: &mut W) -> io::Result<()> {
+        SVGCanvas::write_pdf(self, stream)
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn save_pdf<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        SVGCanvas::save_pdf(self, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::series::LineSeries;
+
+    #[test]
+    fn test_figure_grid() {
+        let mut top = FeatureSVG::with_size(300, 200);
+        top.x_range = Some(CoordinateRange::new(1.0, 5.0));
+        top.y_range = Some(CoordinateRange::new(100.0, 0.0));
+        let mut bottom = FeatureSVG::with_size(300, 200);
+        bottom.x_range = Some(CoordinateRange::new(2.0, 8.0));
+        bottom.y_range = Some(CoordinateRange::new(50.0, 0.0));
+
+        let mut figure = Figure::new(2, 1);
+        figure
+            .set_panel(0, 0, top)
+            .set_panel(1, 0, bottom)
+            .panel_letters(true)
+            .share_x();
+        for panel in figure.panels.iter().flatten() {
+            let x = panel.x_range().unwrap();
+            assert_eq!((x.start, x.end), (1.0, 8.0));
+            let domain = panel.canvas().x_axis.scale.domain;
+            assert_eq!((domain.start, domain.end), (1.0, 8.0));
+        }
+        let y = figure.panel(1, 0).unwrap().y_range().unwrap();
+        assert_eq!((y.start, y.end), (50.0, 0.0));
+
+        // Only the bottom panel labels the shared axis
+        let svg = figure.to_string();
+        assert_eq!(svg.matches("Time (min)").count(), 1);
+        assert_eq!(svg.matches("class=\"panel-letter\"").count(), 2);
+        assert!(svg.contains("\nB\n"));
+
+        let (width, height) = figure.size();
+        assert!(width > 300.0 && height > 400.0 + DEFAULT_FIGURE_SPACING);
+    }
+
+    fn drawn_panel(start: f64, end: f64) -> FeatureSVG {
+        let mut chart = FeatureSVG::with_size(300, 200);
+        chart.x_range = Some(CoordinateRange::new(start, end));
+        chart.y_range = Some(CoordinateRange::new(100.0, 0.0));
+        chart
+            .canvas
+            .update_scales(chart.x_range.unwrap(), chart.y_range.unwrap());
+        chart.add_series(LineSeries::new(
+            vec![(start, 10.0), (end, 90.0)],
+            "trace".into(),
+        ));
+        chart
+    }
+
+    #[test]
+    fn test_share_drawn_panels() {
+        // Panels whose ranges are already the shared ranges keep their series
+        let mut figure = Figure::new(1, 2);
+        figure
+            .set_panel(0, 0, drawn_panel(1.0, 8.0))
+            .set_panel(0, 1, drawn_panel(1.0, 8.0))
+            .share_x()
+            .share_y();
+        assert_eq!(figure.to_string().matches("class=\"trace\"").count(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "after series have been drawn")]
+    fn test_share_rescales_drawn_panel() {
+        let mut figure = Figure::new(1, 2);
+        figure
+            .set_panel(0, 0, drawn_panel(1.0, 5.0))
+            .set_panel(0, 1, drawn_panel(2.0, 8.0))
+            .share_x();
+    }
+}
@@ -0,0 +1,464 @@
+use mzpeaks::{CentroidLike, MZLocated, MZPeakSetType, PeakCollection, Tolerance};
+use svg::node::element::{Group, Line};
+
+use super::chart_regions::{Canvas, RenderCoordinate, TextProps};
+use super::series::{within_segments, PlotSeries, SeriesDescription};
+use crate::mzpaf::{MzPafError, PeakAnnotation};
+use crate::peptide::{match_fragments, Fragment, FragmentMatch, IonType, Peptide, ProFormaError};
+use crate::CoordinateRange;
+
+/// The parameters for matching a peptide's fragment ions against a spectrum
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentAnnotation {
+    pub peptide: Peptide,
+    pub charge: i32,
+    pub tolerance: Tolerance,
+    pub ion_types: Vec<IonType>,
+}
+
+impl FragmentAnnotation {
+    /// Parse `proforma` and match its b and y ions. If `proforma` has a charge state
+    /// it takes precedence over `charge`.
+    pub fn new(proforma: &str, charge: i32, tolerance: Tolerance) -> Result<Self, ProFormaError> {
+        let peptide = Peptide::from_proforma(proforma)?;
+        let charge = peptide.charge.unwrap_or(charge);
+        Ok(Self {
+            peptide,
+            charge,
+            tolerance,
+            ion_types: vec![IonType::B, IonType::Y],
+        })
+    }
+
+    pub fn ion_types(mut self, ion_types: impl IntoIterator<Item = IonType>) -> Self {
+        self.ion_types = ion_types.into_iter().collect();
+        self
+    }
+
+    /// Fragments are considered up to one less than the precursor charge
+    pub fn max_fragment_charge(&self) -> i32 {
+        (self.charge.abs() - 1).max(1)
+    }
+
+    pub fn fragments(&self) -> Vec<Fragment> {
+        self.peptide
+            .fragments(&self.ion_types, self.max_fragment_charge())
+    }
+
+    pub fn match_peaks<C: CentroidLike>(&self, peaks: &MZPeakSetType<C>) -> Vec<FragmentMatch> {
+        match_fragments(&self.fragments(), peaks, self.tolerance)
+    }
+}
+
+/// Draw a peak as a colored stick from the baseline with `label` just above its apex
+fn labeled_stick<X: RenderCoordinate, Y: RenderCoordinate>(
+    canvas: &Canvas<X, Y>,
+    x: X,
+    y: Y,
+    color: &str,
+    label: String,
+    text_props: &TextProps,
+) -> Group {
+    let (x1, y1) = canvas.transform(x, Y::zero());
+    let (x2, y2) = canvas.transform(x, y);
+    let text_props = TextProps {
+        color: color.to_string(),
+        ..text_props.clone()
+    };
+    Group::new()
+        .add(
+            Line::new()
+                .set("x1", x1)
+                .set("y1", y1)
+                .set("x2", x2)
+                .set("y2", y2)
+                .set("stroke", color)
+                .set("stroke-width", 1.5),
+        )
+        .add(
+            Group::new()
+                .set("class", "peak-label")
+                .set("transform", format!("translate({}, {})", x2, y2 - 4.0))
+                .add(text_props.text(label)),
+        )
+}
+
+/// Draw matched fragment peaks as sticks in the color of their ion series, labeled with the
+/// fragment's name.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FragmentSeries<X: RenderCoordinate, Y: RenderCoordinate> {
+    pub points: Vec<(X, Y, Fragment)>,
+    pub description: SeriesDescription,
+    pub text_props: TextProps,
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> FragmentSeries<X, Y> {
+    pub fn new(points: Vec<(X, Y, Fragment)>, description: SeriesDescription) -> Self {
+        Self {
+            points,
+            description,
+            text_props: TextProps {
+                text_size: 0.8,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn from_matches(matches: &[FragmentMatch], description: SeriesDescription) -> Self {
+        let points = matches
+            .iter()
+            .map(|m| {
+                (
+                    X::from(m.mz).unwrap(),
+                    Y::from(m.intensity).unwrap(),
+                    m.fragment,
+                )
+            })
+            .collect();
+        Self::new(points, description)
+    }
+
+    pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        let mut group = Group::new()
+            .set("class", self.description.series_type())
+            .set("id", self.description.id());
+        for (x, y, fragment) in self.points.iter() {
+            if canvas.x_scale().segment_of(*x).is_none() {
+                continue;
+            }
+            group = group.add(labeled_stick(
+                canvas,
+                *x,
+                *y,
+                fragment.ion_type.color(),
+                fragment.label(),
+                &self.text_props,
+            ));
+        }
+        group
+    }
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for FragmentSeries<X, Y> {
+    fn description(&self) -> &SeriesDescription {
+        &self.description
+    }
+
+    fn description_mut(&mut self) -> &mut SeriesDescription {
+        &mut self.description
+    }
+
+    fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        self.to_svg(canvas)
+    }
+
+    fn slice_x(&mut self, start: X, end: X) {
+        self.points.retain(|(x, _, _)| (x >= &start) && (x <= &end));
+    }
+
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points
+            .retain(|(x, _, _)| within_segments(segments, *x));
+    }
+
+    fn slice_y(&mut self, start: Y, end: Y) {
+        self.points.retain(|(_, y, _)| (y >= &start) && (y <= &end));
+    }
+}
+
+/// Draw centroid peaks explained by mzPAF annotations, colored by the ion series of their
+/// first annotation and labeled with all of them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PeakAnnotationSeries<X: RenderCoordinate, Y: RenderCoordinate> {
+    pub points: Vec<(X, Y, Vec<PeakAnnotation>)>,
+    pub description: SeriesDescription,
+    pub text_props: TextProps,
+    /// Whether to include auxiliary (`&`) annotations in the labels
+    pub show_auxiliary: bool,
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> PeakAnnotationSeries<X, Y> {
+    pub fn new(points: Vec<(X, Y, Vec<PeakAnnotation>)>, description: SeriesDescription) -> Self {
+        Self {
+            points,
+            description,
+            text_props: TextProps {
+                text_size: 0.8,
+                ..Default::default()
+            },
+            show_auxiliary: false,
+        }
+    }
+
+    /// Attach mzPAF annotation strings to peaks, given as pairs of an index into `peaks` and
+    /// the annotation text for that peak. Peaks without annotations are not drawn.
+    pub fn from_peaks<C: CentroidLike, S: AsRef<str>>(
+        peaks: &MZPeakSetType<C>,
+        annotations: impl IntoIterator<Item = (usize, S)>,
+        description: SeriesDescription,
+    ) -> Result<Self, MzPafError> {
+        let points = annotations
+            .into_iter()
+            .filter_map(|(i, text)| {
+                (i < peaks.len()).then(|| {
+                    let peak = &peaks[i];
+                    PeakAnnotation::parse_list(text.as_ref()).map(|annotations| {
+                        (
+                            X::from(peak.mz()).unwrap(),
+                            Y::from(peak.intensity()).unwrap(),
+                            annotations,
+                        )
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(points, description))
+    }
+
+    pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        let mut group = Group::new()
+            .set("class", self.description.series_type())
+            .set("id", self.description.id());
+        for (x, y, annotations) in self.points.iter() {
+            if canvas.x_scale().segment_of(*x).is_none() {
+                continue;
+            }
+            let shown: Vec<&PeakAnnotation> = annotations
+                .iter()
+                .filter(|a| self.show_auxiliary || !a.auxiliary)
+                .collect();
+            let Some(first) = shown.first() else {
+                continue;
+            };
+            let label = shown
+                .iter()
+                .map(|a| a.label())
+                .collect::<Vec<_>>()
+                .join(", ");
+            group = group.add(labeled_stick(
+                canvas,
+                *x,
+                *y,
+                first.color(),
+                label,
+                &self.text_props,
+            ));
+        }
+        group
+    }
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for PeakAnnotationSeries<X, Y> {
+    fn description(&self) -> &SeriesDescription {
+        &self.description
+    }
+
+    fn description_mut(&mut self) -> &mut SeriesDescription {
+        &mut self.description
+    }
+
+    fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        self.to_svg(canvas)
+    }
+
+    fn slice_x(&mut self, start: X, end: X) {
+        self.points.retain(|(x, _, _)| (x >= &start) && (x <= &end));
+    }
+
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points
+            .retain(|(x, _, _)| within_segments(segments, *x));
+    }
+
+    fn slice_y(&mut self, start: Y, end: Y) {
+        self.points.retain(|(_, y, _)| (y >= &start) && (y <= &end));
+    }
+}
+
+/// Draw a peptide's sequence with a tick at each cleavage site where a fragment was matched.
+///
+/// N-terminal ions hook down and to the left below the sequence, C-terminal ions hook up and
+/// to the right above it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeptideLadder {
+    pub peptide: Peptide,
+    pub fragments: Vec<Fragment>,
+    pub font_size: f64,
+    /// The largest horizontal distance between residues in pixels
+    pub residue_spacing: f64,
+    /// The distance from the top of the canvas to the top of the ladder in pixels
+    pub top: f64,
+}
+
+impl PeptideLadder {
+    pub fn new(peptide: Peptide, fragments: Vec<Fragment>) -> Self {
+        Self {
+            peptide,
+            fragments,
+            font_size: 16.0,
+            residue_spacing: 24.0,
+            top: 8.0,
+        }
+    }
+
+    pub fn from_matches(peptide: Peptide, matches: &[FragmentMatch]) -> Self {
+        Self::new(peptide, matches.iter().map(|m| m.fragment).collect())
+    }
+
+    /// The ion types matched at the cleavage site after `position` residues
+    fn matched_at(&self, position: usize) -> Vec<IonType> {
+        let n = self.peptide.len();
+        let mut ion_types: Vec<IonType> = self
+            .fragments
+            .iter()
+            .filter(|f| {
+                if f.ion_type.is_n_terminal() {
+                    f.ordinal == position
+                } else {
+                    f.ordinal + position == n
+                }
+            })
+            .map(|f| f.ion_type)
+            .collect();
+        ion_types.sort();
+        ion_types.dedup();
+        ion_types
+    }
+
+    pub fn to_svg<X: RenderCoordinate, Y: RenderCoordinate>(&self, canvas: &Canvas<X, Y>) -> Group {
+        let n = self.peptide.len();
+        let spacing = self
+            .residue_spacing
+            .min(canvas.width as f64 * 0.8 / n.max(1) as f64);
+        let start = (canvas.width as f64 - spacing * n as f64) / 2.0;
+        let baseline = self.top + self.font_size;
+        let text_props = TextProps::default();
+
+        let mut group = Group::new()
+            .set("class", "peptide-ladder")
+            .set("font-size", self.font_size);
+        for (i, residue) in self.peptide.residues.iter().enumerate() {
+            let x = start + spacing * (i as f64 + 0.5);
+            group = group.add(
+                text_props
+                    .text(residue.symbol().to_string())
+                    .set("font-size", self.font_size)
+                    .set("x", x)
+                    .set("y", baseline),
+            );
+        }
+
+        let tick_top = self.top - 2.0;
+        let tick_bottom = baseline + self.font_size * 0.3;
+        for position in 1..n {
+            let ion_types = self.matched_at(position);
+            if ion_types.is_empty() {
+                continue;
+            }
+            let x = start + spacing * position as f64;
+            group = group.add(
+                Line::new()
+                    .set("x1", x)
+                    .set("x2", x)
+                    .set("y1", tick_top)
+                    .set("y2", tick_bottom)
+                    .set("stroke", "black")
+                    .set("stroke-width", 1),
+            );
+            let mut n_terminal = 0.0;
+            let mut c_terminal = 0.0;
+            for ion_type in ion_types {
+                let (y, dx) = if ion_type.is_n_terminal() {
+                    n_terminal += 3.0;
+                    (tick_bottom + n_terminal - 3.0, -spacing * 0.3)
+                } else {
+                    c_terminal += 3.0;
+                    (tick_top - c_terminal + 3.0, spacing * 0.3)
+                };
+                group = group.add(
+                    Line::new()
+                        .set("class", format!("ladder-{}", ion_type.symbol()))
+                        .set("x1", x)
+                        .set("x2", x + dx)
+                        .set("y1", y)
+                        .set("y2", y)
+                        .set("stroke", ion_type.color())
+                        .set("stroke-width", 2),
+                );
+            }
+        }
+        group
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mzpeaks::CentroidPeak;
+
+    #[test]
+    fn test_fragment_annotation() {
+        let annotation = FragmentAnnotation::new("PEPTIDE/2", 1, Tolerance::PPM(10.0)).unwrap();
+        assert_eq!(annotation.charge, 2);
+        assert_eq!(annotation.max_fragment_charge(), 1);
+
+        let peaks: MZPeakSetType<CentroidPeak> = MZPeakSetType::new(vec![
+            CentroidPeak::new(148.0604, 100.0, 0),
+            CentroidPeak::new(227.1026, 50.0, 1),
+            CentroidPeak::new(500.0, 10.0, 2),
+        ]);
+        let matches = annotation.match_peaks(&peaks);
+        assert_eq!(matches.len(), 2);
+
+        let mut canvas: Canvas<f64, f32> = Canvas::new(400, 200);
+        canvas.update_scales(
+            CoordinateRange::new(100.0, 600.0),
+            CoordinateRange::new(100.0, 0.0),
+        );
+        let series: FragmentSeries<f64, f32> =
+            FragmentSeries::from_matches(&matches, "fragments".into());
+        let text = series.to_svg(&canvas).to_string();
+        assert!(text.contains("firebrick"));
+        assert!(text.contains("b2"));
+
+        let ladder = PeptideLadder::from_matches(annotation.peptide.clone(), &matches);
+        assert_eq!(ladder.matched_at(2), vec![IonType::B]);
+        assert_eq!(ladder.matched_at(6), vec![IonType::Y]);
+        let text = ladder.to_svg(&canvas).to_string();
+        assert_eq!(text.matches("ladder-b").count(), 1);
+        assert_eq!(text.matches("ladder-y").count(), 1);
+    }
+
+    #[test]
+    fn test_peak_annotation_series() {
+        let peaks: MZPeakSetType<CentroidPeak> = MZPeakSetType::new(vec![
+            CentroidPeak::new(148.0604, 100.0, 0),
+            CentroidPeak::new(227.1026, 50.0, 1),
+            CentroidPeak::new(500.0, 10.0, 2),
+        ]);
+        let series: PeakAnnotationSeries<f64, f32> = PeakAnnotationSeries::from_peaks(
+            &peaks,
+            [(0, "y1/0.4ppm"), (1, "b2-H2O^2, &y3")],
+            "mzpaf".into(),
+        )
+        .unwrap();
+        assert_eq!(series.points.len(), 2);
+        assert_eq!(series.points[1].2.len(), 2);
+
+        let mut canvas: Canvas<f64, f32> = Canvas::new(400, 200);
+        canvas.update_scales(
+            CoordinateRange::new(100.0, 600.0),
+            CoordinateRange::new(100.0, 0.0),
+        );
+        let text = series.to_svg(&canvas).to_string();
+        assert!(text.contains("y1 (0.4 ppm)"));
+        assert!(text.contains("b2-H₂O²⁺"));
+        assert!(!text.contains("y3"));
+        assert!(text.contains(IonType::B.color()));
+
+        assert!(
+            PeakAnnotationSeries::<f64, f32>::from_peaks(&peaks, [(0, "q1")], "mzpaf".into())
+                .is_err()
+        );
+    }
+}
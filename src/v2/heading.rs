@@ -0,0 +1,259 @@
+use mzdata::meta::DissociationMethodTerm;
+use mzdata::prelude::*;
+use mzdata::spectrum::ScanPolarity;
+use mzpeaks::{CentroidLike, DeconvolutedCentroidLike};
+use svg::node::element::Group;
+
+use super::chart_regions::{Sides, TextProps};
+use super::layout::{default_text_metrics, DEFAULT_FONT_SIZE};
+
+/// The space between the heading and the top of the chart beneath it
+const HEADING_GAP: f64 = 8.0;
+
+/// The template used by [`SpectrumHeader::default`]
+pub const DEFAULT_HEADER_TEMPLATE: &str = "{id}  MS{ms_level}  {time} min  {polarity}[  precursor {precursor_mz}][ ({charge})][  {activation}][ @ {energy}]";
+
+/// A title and subtitle drawn centered above a chart's data area
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub title_props: TextProps,
+    pub subtitle_props: TextProps,
+}
+
+impl Default for Heading {
+    fn default() -> Self {
+        Self {
+            title: None,
+            subtitle: None,
+            title_props: TextProps {
+                text_size: 1.2,
+                ..Default::default()
+            },
+            subtitle_props: TextProps {
+                text_size: 0.85,
+                color: "dimgrey".to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Heading {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.subtitle.is_none()
+    }
+
+    fn lines(&self) -> impl Iterator<Item = (&String, &TextProps)> {
+        self.title
+            .iter()
+            .map(|title| (title, &self.title_props))
+            .chain(
+                self.subtitle
+                    .iter()
+                    .map(|subtitle| (subtitle, &self.subtitle_props)),
+            )
+    }
+
+    /// The height of the heading in pixels, including the gap beneath it
+    pub fn height(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let metrics = default_text_metrics();
+        self.lines()
+            .map(|(_, props)| metrics.text_height(props.text_size * DEFAULT_FONT_SIZE))
+            .sum::<f64>()
+            + HEADING_GAP
+    }
+
+    /// The space the heading needs above the chart
+    pub fn extent(&self) -> Sides {
+        Sides {
+            top: self.height(),
+            ..Default::default()
+        }
+    }
+
+    /// Draw the heading with its lines centered on `center`
+    pub fn to_svg(&self, center: f64) -> Group {
+        let metrics = default_text_metrics();
+        let mut group = Group::new().set("class", "heading");
+        let mut y = 0.0;
+        for (text, props) in self.lines() {
+            let line_height = metrics.text_height(props.text_size * DEFAULT_FONT_SIZE);
+            group = group.add(
+                props
+                    .text(text.clone())
+                    .set("x", center)
+                    .set("y", y + line_height / 2.0)
+                    .set("dy", "0.32em"),
+            );
+            y += line_height;
+        }
+        group
+    }
+}
+
+/// A short name for the dissociation methods usually given by their abbreviation
+fn activation_label(method: &DissociationMethodTerm) -> String {
+    match method {
+        DissociationMethodTerm::BeamTypeCollisionInducedDissociation
+        | DissociationMethodTerm::HigherEnergyBeamTypeCollisionInducedDissociation => {
+            "HCD".to_string()
+        }
+        DissociationMethodTerm::ElectronTransferDissociation => "ETD".to_string(),
+        DissociationMethodTerm::ElectronCaptureDissociation => "ECD".to_string(),
+        DissociationMethodTerm::NegativeElectronTransferDissociation => "NETD".to_string(),
+        DissociationMethodTerm::ElectronActivatedDissociation => "EAD".to_string(),
+        method if method.is_collisional() => "CID".to_string(),
+        method => method.name().to_string(),
+    }
+}
+
+/// Fill the `{field}` placeholders in `template` using `lookup`.
+///
+/// Text between `[` and `]` is only kept if every field it refers to has a value, while a
+/// field without a value outside of brackets is left blank.
+fn fill_template(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut text = String::new();
+    let mut optional: Option<(String, bool)> = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if optional.is_none() => optional = Some((String::new(), true)),
+            ']' if optional.is_some() => {
+                if let Some((part, true)) = optional.take() {
+                    text.push_str(&part);
+                }
+            }
+            '{' => {
+                let field: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                match (optional.as_mut(), lookup(field.trim())) {
+                    (Some((part, _)), Some(value)) => part.push_str(&value),
+                    (Some((_, complete)), None) => *complete = false,
+                    (None, Some(value)) => text.push_str(&value),
+                    (None, None) => {}
+                }
+            }
+            c => match optional.as_mut() {
+                Some((part, _)) => part.push(c),
+                None => text.push(c),
+            },
+        }
+    }
+    if let Some((part, true)) = optional {
+        text.push_str(&part);
+    }
+    text.trim().to_string()
+}
+
+/// Describe a spectrum's provenance from its metadata with a template string.
+///
+/// The template may refer to the fields `{id}`, `{index}`, `{ms_level}`, `{time}`,
+/// `{polarity}`, `{precursor_mz}`, `{charge}`, `{activation}` and `{energy}`. Text between
+/// `[` and `]` is left out when a field it refers to is missing, like the precursor of an MS1
+/// spectrum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpectrumHeader {
+    pub template: String,
+}
+
+impl Default for SpectrumHeader {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEADER_TEMPLATE)
+    }
+}
+
+impl SpectrumHeader {
+    pub fn new(template: impl ToString) -> Self {
+        Self {
+            template: template.to_string(),
+        }
+    }
+
+    pub fn format<C: CentroidLike, D: DeconvolutedCentroidLike, S: SpectrumLike<C, D>>(
+        &self,
+        spectrum: &S,
+    ) -> String {
+        let precursor = spectrum.precursor();
+        let ion = precursor.and_then(|p| p.ions.first());
+        fill_template(&self.template, |field| match field {
+            "id" => Some(spectrum.id().to_string()),
+            "index" => Some(spectrum.index().to_string()),
+            "ms_level" => Some(spectrum.ms_level().to_string()),
+            "time" => Some(format!("{:.2}", spectrum.start_time())),
+            "polarity" => match spectrum.polarity() {
+                ScanPolarity::Positive => Some("positive".to_string()),
+                ScanPolarity::Negative => Some("negative".to_string()),
+                ScanPolarity::Unknown => None,
+            },
+            "precursor_mz" => ion.map(|ion| format!("{:.4}", ion.mz)),
+            "charge" => ion.and_then(|ion| ion.charge).map(|z| {
+                let sign = if z < 0 { '-' } else { '+' };
+                format!("{}{sign}", z.abs())
+            }),
+            "activation" => precursor
+                .and_then(|p| p.activation.method())
+                .map(activation_label),
+            "energy" => precursor
+                .map(|p| p.activation.energy)
+                .filter(|energy| *energy > 0.0)
+                .map(|energy| format!("{energy}")),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mzdata::spectrum::{MultiLayerSpectrum, Precursor, SelectedIon};
+
+    #[test]
+    fn test_spectrum_header() {
+        let mut spectrum: MultiLayerSpectrum = MultiLayerSpectrum::default();
+        let description = spectrum.description_mut();
+        description.id = "scan=12".to_string();
+        description.ms_level = 1;
+        description.polarity = ScanPolarity::Positive;
+        description.acquisition.first_scan_mut().unwrap().start_time = 12.3456;
+        let header = SpectrumHeader::default();
+        assert_eq!(
+            header.format(&spectrum),
+            "scan=12  MS1  12.35 min  positive"
+        );
+
+        let description = spectrum.description_mut();
+        description.ms_level = 2;
+        let mut precursor = Precursor::default();
+        precursor.ions.push(SelectedIon {
+            mz: 562.7582,
+            charge: Some(2),
+            ..Default::default()
+        });
+        precursor
+            .activation
+            .methods_mut()
+            .push(DissociationMethodTerm::BeamTypeCollisionInducedDissociation);
+        precursor.activation.energy = 30.0;
+        description.precursor = Some(precursor);
+        assert_eq!(
+            header.format(&spectrum),
+            "scan=12  MS2  12.35 min  positive  precursor 562.7582 (2+)  HCD @ 30"
+        );
+        assert_eq!(
+            SpectrumHeader::new("[{charge} ]#{index}{unknown}").format(&spectrum),
+            "2+ #0"
+        );
+
+        let mut heading = Heading::default();
+        assert_eq!(heading.height(), 0.0);
+        heading.subtitle = Some(header.format(&spectrum));
+        let subtitle_height = heading.height();
+        heading.title = Some("Peptide".to_string());
+        assert!(heading.extent().top > subtitle_height);
+        assert!(heading.to_svg(100.0).to_string().contains("Peptide"));
+    }
+}
@@ -0,0 +1,523 @@
+use mzdata::prelude::*;
+use mzpeaks::{CentroidLike, DeconvolutedCentroidLike};
+use svg::node::element::{Group, Rectangle};
+
+use super::chart_regions::{
+    AxisOrientation, AxisProps, AxisTickLabelStyle, Canvas, RenderCoordinate,
+};
+use super::layout::default_text_metrics;
+use super::series::{PlotSeries, SeriesDescription};
+use crate::linear::Scale;
+use crate::{CoordinateRange, ScaleType};
+
+const VIRIDIS: &[[u8; 3]] = &[
+    [68, 1, 84],
+    [71, 45, 123],
+    [59, 82, 139],
+    [44, 114, 142],
+    [33, 145, 140],
+    [40, 174, 128],
+    [94, 201, 98],
+    [173, 220, 48],
+    [253, 231, 37],
+];
+
+const MAGMA: &[[u8; 3]] = &[
+    [0, 0, 4],
+    [28, 16, 68],
+    [79, 18, 123],
+    [129, 37, 129],
+    [181, 54, 122],
+    [229, 89, 100],
+    [251, 135, 97],
+    [254, 194, 135],
+    [252, 253, 191],
+];
+
+const INFERNO: &[[u8; 3]] = &[
+    [0, 0, 4],
+    [31, 12, 72],
+    [85, 15, 109],
+    [136, 34, 106],
+    [186, 54, 85],
+    [227, 89, 51],
+    [249, 142, 9],
+    [249, 203, 53],
+    [252, 255, 164],
+];
+
+const GREYS: &[[u8; 3]] = &[[255, 255, 255], [0, 0, 0]];
+
+/// Map values between 0 and 1 onto colors by interpolating between evenly spaced stops
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Magma,
+    Inferno,
+    /// From white to black
+    Greys,
+    /// Evenly spaced RGB stops, from low to high
+    Custom(Vec<[u8; 3]>),
+}
+
+impl Colormap {
+    pub fn stops(&self) -> &[[u8; 3]] {
+        match self {
+            Colormap::Viridis => VIRIDIS,
+            Colormap::Magma => MAGMA,
+            Colormap::Inferno => INFERNO,
+            Colormap::Greys => GREYS,
+            Colormap::Custom(stops) => stops,
+        }
+    }
+
+    /// The color at `position`, clamped between 0 and 1, as a hex string
+    pub fn color(&self, position: f64) -> String {
+        let stops = self.stops();
+        let [r, g, b] = match stops.len() {
+            0 => [0, 0, 0],
+            1 => stops[0],
+            n => {
+                let position = if position.is_nan() {
+                    0.0
+                } else {
+                    position.clamp(0.0, 1.0)
+                };
+                let scaled = position * (n - 1) as f64;
+                let i = (scaled.floor() as usize).min(n - 2);
+                let frac = scaled - i as f64;
+                let (lo, hi) = (stops[i], stops[i + 1]);
+                [0, 1, 2]
+                    .map(|c| (lo[c] as f64 + (hi[c] as f64 - lo[c] as f64) * frac).round() as u8)
+            }
+        };
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+/// Sum intensity over a regular 2D grid, drawing each non-empty cell colored by a
+/// [`Colormap`].
+///
+/// The grid's first coordinate is drawn horizontally and its second vertically, so an
+/// LC-MS run is usually binned as retention time × m/z.
+#[derive(Debug, Clone)]
+pub struct HeatmapSeries<X: RenderCoordinate, Y: RenderCoordinate> {
+    pub x_range: CoordinateRange<X>,
+    pub y_range: CoordinateRange<Y>,
+    pub x_bins: usize,
+    pub y_bins: usize,
+    /// The summed intensity of each cell, row by row along `y`
+    pub values: Vec<f64>,
+    pub colormap: Colormap,
+    /// How cell intensities are mapped onto the colormap
+    pub intensity_scale: ScaleType,
+    pub description: SeriesDescription,
+    x_window: Option<(X, X)>,
+    y_window: Option<(Y, Y)>,
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> HeatmapSeries<X, Y> {
+    /// Create an empty grid of `x_bins` × `y_bins` cells spanning `x_range` and `y_range`
+    pub fn new(
+        x_range: CoordinateRange<X>,
+        y_range: CoordinateRange<Y>,
+        x_bins: usize,
+        y_bins: usize,
+        description: SeriesDescription,
+    ) -> Self {
+        let x_bins = x_bins.max(1);
+        let y_bins = y_bins.max(1);
+        Self {
+            x_range,
+            y_range,
+            x_bins,
+            y_bins,
+            values: vec![0.0; x_bins * y_bins],
+            colormap: Colormap::default(),
+            intensity_scale: ScaleType::default(),
+            description,
+            x_window: None,
+            y_window: None,
+        }
+    }
+
+    /// Bin `(x, y, intensity)` triples over a grid spanning their extents
+    pub fn from_triples(
+        points: impl IntoIterator<Item = (X, Y, f32)>,
+        x_bins: usize,
+        y_bins: usize,
+        description: SeriesDescription,
+    ) -> Self {
+        let points: Vec<_> = points.into_iter().collect();
+        let (x_min, x_max) = points
+            .iter()
+            .fold((X::infinity(), X::neg_infinity()), |(lo, hi), (x, _, _)| {
+                (lo.min(*x), hi.max(*x))
+            });
+        let (y_min, y_max) = points
+            .iter()
+            .fold((Y::infinity(), Y::neg_infinity()), |(lo, hi), (_, y, _)| {
+                (lo.min(*y), hi.max(*y))
+            });
+        let (x_min, x_max) = if points.is_empty() {
+            (X::zero(), X::one())
+        } else {
+            (x_min, x_max)
+        };
+        let (y_min, y_max) = if points.is_empty() {
+            (Y::zero(), Y::one())
+        } else {
+            (y_min, y_max)
+        };
+        let mut inst = Self::new(
+            CoordinateRange::new(x_min, x_max),
+            CoordinateRange::new(y_min, y_max),
+            x_bins,
+            y_bins,
+            description,
+        );
+        for (x, y, z) in points {
+            inst.add(x, y, z);
+        }
+        inst
+    }
+
+    /// Bin the signal of each spectrum by its scan start time and m/z, over a grid spanning
+    /// the run's extents.
+    ///
+    /// Every point is held in memory until the extents are known. To bin a run in a single
+    /// pass, create the grid with [`HeatmapSeries::new`] and call
+    /// [`HeatmapSeries::add_spectrum`] instead. Filter the spectra beforehand to draw only
+    /// a single MS level.
+    pub fn from_spectra<C: CentroidLike, D: DeconvolutedCentroidLike, S: SpectrumLike<C, D>>(
+        spectra: impl IntoIterator<Item = S>,
+        x_bins: usize,
+        y_bins: usize,
+        description: SeriesDescription,
+    ) -> Self {
+        let points = spectra.into_iter().flat_map(|spectrum| {
+            let time = X::from(spectrum.start_time()).unwrap();
+            spectrum
+                .peaks()
+                .iter()
+                .map(|p| (time, Y::from(p.mz).unwrap(), p.intensity))
+                .collect::<Vec<_>>()
+        });
+        Self::from_triples(points, x_bins, y_bins, description)
+    }
+
+    /// Add the signal of `spectrum` at its scan start time
+    pub fn add_spectrum<C: CentroidLike, D: DeconvolutedCentroidLike>(
+        &mut self,
+        spectrum: &impl SpectrumLike<C, D>,
+    ) {
+        let time = X::from(spectrum.start_time()).unwrap();
+        for p in spectrum.peaks().iter() {
+            self.add(time, Y::from(p.mz).unwrap(), p.intensity);
+        }
+    }
+
+    pub fn colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    pub fn intensity_scale(mut self, scale_type: ScaleType) -> Self {
+        self.intensity_scale = scale_type;
+        self
+    }
+
+    fn bin_of<T: RenderCoordinate>(
+        value: T,
+        range: &CoordinateRange<T>,
+        bins: usize,
+    ) -> Option<usize> {
+        if !range.contains(value) {
+            return None;
+        }
+        let width = range.max() - range.min();
+        if width <= T::zero() {
+            return Some(0);
+        }
+        let frac = ((value - range.min()) / width).to_f64().unwrap();
+        Some(((frac * bins as f64) as usize).min(bins - 1))
+    }
+
+    fn edges_of<T: RenderCoordinate>(
+        index: usize,
+        range: &CoordinateRange<T>,
+        bins: usize,
+    ) -> (T, T) {
+        let width = (range.max() - range.min()) / T::from(bins).unwrap();
+        let start = range.min() + width * T::from(index).unwrap();
+        (start, start + width)
+    }
+
+    /// Add `intensity` to the cell containing `(x, y)`, returning `false` if the point
+    /// lies outside the grid
+    pub fn add(&mut self, x: X, y: Y, intensity: f32) -> bool {
+        let i = Self::bin_of(x, &self.x_range, self.x_bins);
+        let j = Self::bin_of(y, &self.y_range, self.y_bins);
+        match (i, j) {
+            (Some(i), Some(j)) => {
+                self.values[j * self.x_bins + i] += intensity as f64;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        self.values[j * self.x_bins + i]
+    }
+
+    /// The total intensity in each column, at the center of the column
+    pub fn project_x(&self) -> Vec<(X, f64)> {
+        (0..self.x_bins)
+            .map(|i| {
+                let (start, end) = Self::edges_of(i, &self.x_range, self.x_bins);
+                let total = (0..self.y_bins).map(|j| self.get(i, j)).sum();
+                ((start + end) / X::from(2.0).unwrap(), total)
+            })
+            .collect()
+    }
+
+    /// The total intensity in each row, at the center of the row
+    pub fn project_y(&self) -> Vec<(Y, f64)> {
+        (0..self.y_bins)
+            .map(|j| {
+                let (start, end) = Self::edges_of(j, &self.y_range, self.y_bins);
+                let total = (0..self.x_bins).map(|i| self.get(i, j)).sum();
+                ((start + end) / Y::from(2.0).unwrap(), total)
+            })
+            .collect()
+    }
+
+    pub fn max_value(&self) -> f64 {
+        self.values.iter().copied().fold(0.0, f64::max)
+    }
+
+    /// The scale mapping cell intensities onto colormap positions
+    pub fn color_scale(&self) -> Scale<f64> {
+        let max = self.max_value();
+        Scale::new(
+            CoordinateRange::new(0.0, if max > 0.0 { max } else { 1.0 }),
+            CoordinateRange::new(0.0, 1.0),
+        )
+        .with_scale_type(self.intensity_scale)
+    }
+
+    pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        let color_scale = self.color_scale();
+        let mut group = Group::new()
+            .set("class", self.series_type())
+            .set("id", self.series_id())
+            .set("shape-rendering", "crispEdges");
+        for j in 0..self.y_bins {
+            let (y0, y1) = Self::edges_of(j, &self.y_range, self.y_bins);
+            let y_mid = (y0 + y1) / Y::from(2.0).unwrap();
+            if self
+                .y_window
+                .is_some_and(|(lo, hi)| y_mid < lo.min(hi) || y_mid > lo.max(hi))
+            {
+                continue;
+            }
+            for i in 0..self.x_bins {
+                let value = self.get(i, j);
+                if value <= 0.0 {
+                    continue;
+                }
+                let (x0, x1) = Self::edges_of(i, &self.x_range, self.x_bins);
+                let x_mid = (x0 + x1) / X::from(2.0).unwrap();
+                if canvas.x_scale().segment_of(x_mid).is_none()
+                    || self
+                        .x_window
+                        .is_some_and(|(lo, hi)| x_mid < lo.min(hi) || x_mid > lo.max(hi))
+                {
+                    continue;
+                }
+                let (px0, py0) = canvas.transform(x0, y0);
+                let (px1, py1) = canvas.transform(x1, y1);
+                group = group.add(
+                    Rectangle::new()
+                        .set("x", px0.min(px1))
+                        .set("y", py0.min(py1))
+                        .set("width", (px1 - px0).abs())
+                        .set("height", (py1 - py0).abs())
+                        .set("fill", self.colormap.color(color_scale.transform(value))),
+                );
+            }
+        }
+        group
+    }
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for HeatmapSeries<X, Y> {
+    fn description(&self) -> &SeriesDescription {
+        &self.description
+    }
+
+    fn description_mut(&mut self) -> &mut SeriesDescription {
+        &mut self.description
+    }
+
+    fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        self.to_svg(canvas)
+    }
+
+    fn slice_x(&mut self, start: X, end: X) {
+        self.x_window = Some((start, end));
+    }
+
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        // Cells between segments are skipped when drawing
+        let start = segments.iter().map(|s| s.min()).fold(X::infinity(), X::min);
+        let end = segments
+            .iter()
+            .map(|s| s.max())
+            .fold(X::neg_infinity(), X::max);
+        self.x_window = Some((start, end));
+    }
+
+    fn slice_y(&mut self, start: Y, end: Y) {
+        self.y_window = Some((start, end));
+    }
+}
+
+pub const DEFAULT_COLOR_BAR_WIDTH: f64 = 15.0;
+pub const DEFAULT_COLOR_BAR_GAP: f64 = 20.0;
+const COLOR_BAR_STEPS: usize = 64;
+
+/// A vertical legend relating the colors of a [`HeatmapSeries`] to intensities
+#[derive(Debug, Clone)]
+pub struct ColorBar {
+    pub colormap: Colormap,
+    /// The intensities at the bottom and top of the bar
+    pub domain: CoordinateRange<f64>,
+    pub scale_type: ScaleType,
+    /// The width of the colored bar in pixels
+    pub width: f64,
+    /// The space between the data area and the bar in pixels
+    pub gap: f64,
+    pub axis: AxisProps<f64>,
+}
+
+impl ColorBar {
+    pub fn new(colormap: Colormap, domain: CoordinateRange<f64>, scale_type: ScaleType) -> Self {
+        Self {
+            colormap,
+            domain,
+            scale_type,
+            width: DEFAULT_COLOR_BAR_WIDTH,
+            gap: DEFAULT_COLOR_BAR_GAP,
+            axis: AxisProps::new(AxisOrientation::Right)
+                .label("Intensity")
+                .tick_format(AxisTickLabelStyle::SciNot(1))
+                .id("color-bar-axis"),
+        }
+    }
+
+    /// A color bar matching the colors `series` was drawn with
+    pub fn from_series<X: RenderCoordinate, Y: RenderCoordinate>(
+        series: &HeatmapSeries<X, Y>,
+    ) -> Self {
+        let scale = series.color_scale();
+        Self::new(series.colormap.clone(), scale.domain, scale.scale_type)
+    }
+
+    pub fn label<S: ToString>(mut self, label: S) -> Self {
+        self.axis.set_label(label);
+        self
+    }
+
+    fn scale(&self, height: usize) -> Scale<f64> {
+        Scale::new(self.domain, CoordinateRange::new(height as f64, 0.0))
+            .with_scale_type(self.scale_type)
+    }
+
+    /// The horizontal space the bar and its axis take up beside a data area `height`
+    /// pixels tall
+    pub fn extent(&self, height: usize) -> f64 {
+        self.gap + self.width + self.axis.extent(self.scale(height), default_text_metrics())
+    }
+
+    /// Draw the bar beside a data area `height` pixels tall, with the bar's left edge at
+    /// the origin
+    pub fn to_svg(&self, height: usize) -> Group {
+        let scale = self.scale(height);
+        let step = height as f64 / COLOR_BAR_STEPS as f64;
+        let mut bar = Group::new().set("shape-rendering", "crispEdges");
+        for i in 0..COLOR_BAR_STEPS {
+            let position = (i as f64 + 0.5) / COLOR_BAR_STEPS as f64;
+            bar = bar.add(
+                Rectangle::new()
+                    .set("x", 0)
+                    .set("y", height as f64 - (i + 1) as f64 * step)
+                    .set("width", self.width)
+                    // Overlap neighboring steps slightly so no seams show between them
+                    .set("height", step + 0.5)
+                    .set("fill", self.colormap.color(position)),
+            );
+        }
+        bar = bar.add(
+            Rectangle::new()
+                .set("x", 0)
+                .set("y", 0)
+                .set("width", self.width)
+                .set("height", height)
+                .set("fill", "none")
+                .set("stroke", "black")
+                .set("stroke-width", 0.75),
+        );
+        let frame: Canvas<f64, f64> = Canvas::new(self.width.round() as usize, height);
+        Group::new()
+            .set("class", "color-bar")
+            .add(bar)
+            .add(self.axis.to_svg(scale, &frame))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_colormap() {
+        assert_eq!(Colormap::Viridis.color(0.0), "#440154");
+        assert_eq!(Colormap::Viridis.color(1.0), "#fde725");
+        assert_eq!(Colormap::Greys.color(0.5), "#808080");
+        assert_eq!(Colormap::Greys.color(2.0), "#000000");
+    }
+
+    #[test]
+    fn test_heatmap_series() {
+        let points = [
+            (0.0, 100.0, 1.0),
+            (0.1, 101.0, 2.0),
+            (10.0, 200.0, 4.0),
+            (5.0, 150.0, 8.0),
+        ];
+        let series: HeatmapSeries<f64, f32> =
+            HeatmapSeries::from_triples(points, 10, 10, "heatmap".into());
+        assert_eq!(series.get(0, 0), 3.0);
+        assert_eq!(series.get(9, 9), 4.0);
+        assert_eq!(series.get(5, 5), 8.0);
+        assert_eq!(series.max_value(), 8.0);
+        assert_eq!(series.values.iter().filter(|v| **v > 0.0).count(), 3);
+        assert_eq!(series.project_x()[0], (0.5, 3.0));
+        assert_eq!(series.project_y()[5], (155.0, 8.0));
+
+        let mut canvas: Canvas<f64, f32> = Canvas::new(100, 100);
+        canvas.update_scales(
+            CoordinateRange::new(0.0, 10.0),
+            CoordinateRange::new(200.0, 100.0),
+        );
+        let svg = series.to_svg(&canvas).to_string();
+        assert_eq!(svg.matches("<rect").count(), 3);
+
+        let bar = ColorBar::from_series(&series.intensity_scale(ScaleType::log10()));
+        assert!(bar.extent(100) > bar.gap + bar.width);
+        assert!(bar.to_svg(100).to_string().contains("color-bar"));
+    }
+}
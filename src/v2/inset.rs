@@ -0,0 +1,182 @@
+use svg::node::element::{Group, Line, Rectangle};
+
+use super::chart_regions::{
+    AxisOrientation, AxisProps, AxisTickLabelStyle, Canvas, DrawBox, Sides,
+};
+use crate::CoordinateRange;
+
+/// The space between an inset's axes and the edge of the enclosing data area
+const INSET_PADDING: f64 = 8.0;
+
+/// Which corner of a chart's data area an [`Inset`] is drawn in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InsetCorner {
+    #[default]
+    TopRight,
+    TopLeft,
+    BottomRight,
+    BottomLeft,
+}
+
+impl InsetCorner {
+    pub fn is_top(&self) -> bool {
+        matches!(self, Self::TopRight | Self::TopLeft)
+    }
+
+    pub fn is_right(&self) -> bool {
+        matches!(self, Self::TopRight | Self::BottomRight)
+    }
+}
+
+/// A magnified view of an m/z window drawn in a corner of a chart with its own axes, linked
+/// to the region it shows by a rectangle and connector lines
+#[derive(Debug, Clone)]
+pub struct Inset {
+    pub x_range: CoordinateRange<f64>,
+    /// The intensity range to show, fitted to the signal in [`Inset::x_range`] if not given
+    pub y_range: Option<CoordinateRange<f32>>,
+    /// The size of the inset's data area in pixels
+    pub width: usize,
+    pub height: usize,
+    pub corner: InsetCorner,
+    pub xticks: AxisProps<f64>,
+    pub yticks: AxisProps<f32>,
+    /// The color of the source region's outline and the connector lines
+    pub stroke: String,
+    /// Whether to draw lines from the source region to the inset
+    pub connectors: bool,
+}
+
+impl Inset {
+    pub fn new(start: f64, end: f64) -> Self {
+        Self {
+            x_range: CoordinateRange::new(start, end),
+            y_range: None,
+            width: 240,
+            height: 140,
+            corner: InsetCorner::default(),
+            xticks: AxisProps::new(AxisOrientation::Bottom)
+                .tick_count(3)
+                .id("inset-x-axis"),
+            yticks: AxisProps::new(AxisOrientation::Left)
+                .tick_format(AxisTickLabelStyle::SciNot(1))
+                .tick_count(3)
+                .id("inset-y-axis"),
+            stroke: "grey".to_string(),
+            connectors: true,
+        }
+    }
+
+    pub fn y_range(mut self, start: f32, end: f32) -> Self {
+        self.y_range = Some(CoordinateRange::new(start, end));
+        self
+    }
+
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn corner(mut self, corner: InsetCorner) -> Self {
+        self.corner = corner;
+        self
+    }
+
+    pub fn connectors(mut self, connectors: bool) -> Self {
+        self.connectors = connectors;
+        self
+    }
+
+    /// Place the inset's data area in its corner of `parent`, leaving room for axes taking up
+    /// `margins`
+    pub fn draw_box(
+        &self,
+        parent: &Canvas<f64, f32>,
+        margins: &Sides,
+        y_range: CoordinateRange<f32>,
+    ) -> DrawBox<f64, f32> {
+        let x = if self.corner.is_right() {
+            parent.width as f64 - self.width as f64 - margins.right - INSET_PADDING
+        } else {
+            margins.left + INSET_PADDING
+        };
+        let y = if self.corner.is_top() {
+            margins.top + INSET_PADDING
+        } else {
+            parent.height as f64 - self.height as f64 - margins.bottom - INSET_PADDING
+        };
+        let mut draw_box = DrawBox::new(self.width, self.height, Vec::new(), (x, y));
+        draw_box.update_scales(self.x_range, y_range);
+        draw_box
+    }
+
+    /// Draw `chart`, the inset's data area and axes rendered with `margins`, over `parent`
+    /// with the region it magnifies outlined
+    pub fn to_svg(
+        &self,
+        parent: &Canvas<f64, f32>,
+        chart: Group,
+        margins: &Sides,
+        y_range: CoordinateRange<f32>,
+    ) -> Group {
+        let mut draw_box = self.draw_box(parent, margins, y_range);
+        draw_box.push_layer(
+            Group::new().set("class", "inset-background").add(
+                Rectangle::new()
+                    .set("x", -margins.left)
+                    .set("y", -margins.top)
+                    .set("width", self.width as f64 + margins.horizontal())
+                    .set("height", self.height as f64 + margins.vertical())
+                    .set("fill", "white"),
+            ),
+        );
+        draw_box.push_layer(
+            Group::new()
+                .set(
+                    "transform",
+                    format!("translate({}, {})", -margins.left, -margins.top),
+                )
+                .add(chart),
+        );
+
+        let (x0, y0) = parent.transform(self.x_range.start, y_range.max());
+        let (x1, y1) = parent.transform(self.x_range.end, y_range.min());
+        let (x0, x1) = (x0.min(x1), x0.max(x1));
+        let (y0, y1) = (y0.min(y1).max(0.0), y0.max(y1).min(parent.height as f64));
+        let mut group = Group::new().set("class", "inset").add(
+            Rectangle::new()
+                .set("class", "inset-region")
+                .set("x", x0)
+                .set("y", y0)
+                .set("width", x1 - x0)
+                .set("height", y1 - y0)
+                .set("fill", "none")
+                .set("stroke", self.stroke.clone())
+                .set("stroke-dasharray", "4 2"),
+        );
+        if self.connectors {
+            // Link the edge of the region nearest the inset to the inset's facing edge
+            let (region_y, inset_y) = if self.corner.is_top() {
+                (y0, draw_box.origin.1 + self.height as f64 + margins.bottom)
+            } else {
+                (y1, draw_box.origin.1 - margins.top)
+            };
+            let edges = [self.x_range.min(), self.x_range.max()];
+            for (region_x, mz) in [x0, x1].into_iter().zip(edges) {
+                let (inset_x, _) = draw_box.transform_to_parent(mz, y_range.min());
+                group = group.add(
+                    Line::new()
+                        .set("class", "inset-connector")
+                        .set("x1", region_x)
+                        .set("y1", region_y)
+                        .set("x2", inset_x)
+                        .set("y2", inset_y)
+                        .set("stroke", self.stroke.clone())
+                        .set("stroke-width", 0.75),
+                );
+            }
+        }
+        group.add(draw_box.to_svg())
+    }
+}
@@ -0,0 +1,493 @@
+use mzpeaks::{DeconvolutedCentroidLike, MZLocated};
+
+use super::chart_regions::{
+    Canvas, HorizontalAlignment, RenderCoordinate, TextProps, TickFormatter,
+};
+use super::layout::{TextMetrics, DEFAULT_FONT_SIZE};
+use super::series::{AnnotationSeries, SeriesDescription};
+use crate::CoordinateRange;
+
+/// Keep the `n` items with the greatest `intensity`, preserving their original order
+pub(crate) fn top_n_by_intensity<T>(
+    items: Vec<T>,
+    n: usize,
+    intensity: impl Fn(&T) -> f32,
+) -> Vec<T> {
+    let mut ranked: Vec<(usize, f32)> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (i, intensity(item)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut keep = vec![false; items.len()];
+    for (i, _) in ranked.into_iter().take(n) {
+        keep[i] = true;
+    }
+    items
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(item, keep)| keep.then_some(item))
+        .collect()
+}
+
+/// Drop points which would be drawn closer than `min_spacing` pixels horizontally to a more
+/// intense point that was kept, preserving the original order
+pub(crate) fn thin_by_spacing<X: RenderCoordinate, Y: RenderCoordinate>(
+    points: Vec<(X, Y)>,
+    canvas: &Canvas<X, Y>,
+    min_spacing: f64,
+) -> Vec<(X, Y)> {
+    if min_spacing <= 0.0 {
+        return points;
+    }
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|a, b| {
+        let a = points[*a].1.to_f64().unwrap();
+        let b = points[*b].1.to_f64().unwrap();
+        b.total_cmp(&a)
+    });
+    let mut placed: Vec<f64> = Vec::new();
+    let mut keep = vec![false; points.len()];
+    for i in order {
+        let x = canvas.x_scale().transform(points[i].0).to_f64().unwrap();
+        if placed.iter().all(|p| (p - x).abs() >= min_spacing) {
+            placed.push(x);
+            keep[i] = true;
+        }
+    }
+    points
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(point, keep)| keep.then_some(point))
+        .collect()
+}
+
+/// How to choose which peaks to label
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PeakSelection {
+    /// The `n` most intense peaks
+    TopN(usize),
+    /// Every peak at least this intense
+    MinIntensity(f32),
+}
+
+/// How to format a peak's label from its m/z
+#[derive(Debug, Clone, PartialEq)]
+pub enum PeakLabelFormat {
+    /// The m/z with this many decimal places
+    Precision(usize),
+    Custom(TickFormatter),
+}
+
+impl PeakLabelFormat {
+    pub fn custom(formatter: impl Fn(f64) -> String + Send + Sync + 'static) -> Self {
+        Self::Custom(TickFormatter::new(formatter))
+    }
+
+    pub fn format(&self, mz: f64) -> String {
+        match self {
+            PeakLabelFormat::Precision(precision) => format!("{mz:.precision$}"),
+            PeakLabelFormat::Custom(formatter) => formatter.format(mz),
+        }
+    }
+}
+
+/// Label the most prominent peaks in view with their m/z
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakLabels {
+    pub selection: PeakSelection,
+    /// The minimum horizontal distance between labels in pixels. When two selected peaks are
+    /// closer than this, only the more intense one is labeled.
+    pub min_spacing: f64,
+    pub format: PeakLabelFormat,
+    pub text_props: TextProps,
+    pub layout: LabelLayout,
+}
+
+impl Default for PeakLabels {
+    fn default() -> Self {
+        Self {
+            selection: PeakSelection::TopN(10),
+            min_spacing: 30.0,
+            format: PeakLabelFormat::Precision(4),
+            text_props: TextProps {
+                text_size: 0.8,
+                ..Default::default()
+            },
+            layout: LabelLayout::default(),
+        }
+    }
+}
+
+impl PeakLabels {
+    pub fn new(selection: PeakSelection) -> Self {
+        Self {
+            selection,
+            ..Default::default()
+        }
+    }
+
+    pub fn top_n(n: usize) -> Self {
+        Self::new(PeakSelection::TopN(n))
+    }
+
+    pub fn min_intensity(threshold: f32) -> Self {
+        Self::new(PeakSelection::MinIntensity(threshold))
+    }
+
+    pub fn min_spacing(mut self, min_spacing: f64) -> Self {
+        self.min_spacing = min_spacing;
+        self
+    }
+
+    pub fn format(mut self, format: PeakLabelFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn text_props(mut self, text_props: TextProps) -> Self {
+        self.text_props = text_props;
+        self
+    }
+
+    pub fn layout(mut self, layout: LabelLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Choose which of `points` to label among those visible on `canvas`
+    pub fn select<X: RenderCoordinate, Y: RenderCoordinate>(
+        &self,
+        points: impl Iterator<Item = (X, Y)>,
+        canvas: &Canvas<X, Y>,
+    ) -> Vec<(X, Y)> {
+        let domain = &canvas.x_axis.scale.domain;
+        let visible: Vec<(X, Y)> = points
+            .filter(|(x, _)| domain.contains(*x) && canvas.x_scale().segment_of(*x).is_some())
+            .collect();
+        // Resolve crowding first so that a dense cluster doesn't use up the whole top N
+        let visible = thin_by_spacing(visible, canvas, self.min_spacing);
+        match self.selection {
+            PeakSelection::TopN(n) => top_n_by_intensity(visible, n, |(_, y)| y.to_f32().unwrap()),
+            PeakSelection::MinIntensity(threshold) => visible
+                .into_iter()
+                .filter(|(_, y)| y.to_f32().unwrap() >= threshold)
+                .collect(),
+        }
+    }
+
+    pub fn to_series<X: RenderCoordinate, Y: RenderCoordinate>(
+        &self,
+        points: impl Iterator<Item = (X, Y)>,
+        canvas: &Canvas<X, Y>,
+    ) -> AnnotationSeries<X, Y> {
+        let points = self
+            .select(points, canvas)
+            .into_iter()
+            .map(|(x, y)| (x, y, self.format.format(x.to_f64().unwrap())))
+            .collect();
+        AnnotationSeries::new(
+            points,
+            SeriesDescription::from("peak-labels"),
+            self.text_props.clone(),
+        )
+        .layout(self.layout)
+    }
+}
+
+/// Which properties of a deconvoluted peak to show in its label
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DeconvolutedLabelFields {
+    /// `z=2`
+    Charge,
+    /// `1234.5678`
+    NeutralMass,
+    /// `1234.5678 (z=2)`
+    #[default]
+    Both,
+}
+
+/// Label the most intense deconvoluted peaks with their charge state and/or neutral mass
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeconvolutedLabels {
+    /// The number of peaks to label
+    pub top_n: usize,
+    pub fields: DeconvolutedLabelFields,
+    /// The number of decimal places to show neutral masses with
+    pub mass_precision: usize,
+    pub text_props: TextProps,
+}
+
+impl Default for DeconvolutedLabels {
+    fn default() -> Self {
+        Self {
+            top_n: 10,
+            fields: DeconvolutedLabelFields::default(),
+            mass_precision: 4,
+            text_props: TextProps {
+                text_size: 0.8,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl DeconvolutedLabels {
+    pub fn new(top_n: usize) -> Self {
+        Self {
+            top_n,
+            ..Default::default()
+        }
+    }
+
+    pub fn fields(mut self, fields: DeconvolutedLabelFields) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn mass_precision(mut self, mass_precision: usize) -> Self {
+        self.mass_precision = mass_precision;
+        self
+    }
+
+    pub fn text_props(mut self, text_props: TextProps) -> Self {
+        self.text_props = text_props;
+        self
+    }
+
+    pub fn label<D: DeconvolutedCentroidLike>(&self, peak: &D) -> String {
+        let peak = peak.as_centroid();
+        let precision = self.mass_precision;
+        match self.fields {
+            DeconvolutedLabelFields::Charge => format!("z={}", peak.charge),
+            DeconvolutedLabelFields::NeutralMass => {
+                format!("{:.precision$}", peak.neutral_mass)
+            }
+            DeconvolutedLabelFields::Both => {
+                format!("{:.precision$} (z={})", peak.neutral_mass, peak.charge)
+            }
+        }
+    }
+
+    /// Build the labels for the [`DeconvolutedLabels::top_n`] most intense `peaks` whose m/z
+    /// lies within `x_range`
+    pub fn to_series<'a, D: DeconvolutedCentroidLike + MZLocated + 'a>(
+        &self,
+        peaks: impl Iterator<Item = &'a D>,
+        x_range: &CoordinateRange<f64>,
+    ) -> AnnotationSeries<f64, f32> {
+        let candidates: Vec<&D> = peaks.filter(|p| x_range.contains(p.mz())).collect();
+        let points = top_n_by_intensity(candidates, self.top_n, |p| p.intensity())
+            .into_iter()
+            .map(|p| (p.mz(), p.intensity(), self.label(p)))
+            .collect();
+        AnnotationSeries::new(
+            points,
+            SeriesDescription::from("deconvoluted-labels"),
+            self.text_props.clone(),
+        )
+    }
+}
+
+/// The pixel-space extent of a rendered label, with `y` being the top edge
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LabelBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl LabelBox {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Estimate the box `text` occupies when drawn with `text_props` with its baseline
+    /// anchored at `(x, y)`
+    pub fn for_text(
+        text: &str,
+        x: f64,
+        y: f64,
+        text_props: &TextProps,
+        metrics: &dyn TextMetrics,
+    ) -> Self {
+        let font_size = text_props.text_size * DEFAULT_FONT_SIZE;
+        let width = metrics.text_width(text, font_size);
+        let height = metrics.text_height(font_size);
+        let left = match text_props.horizontal_alignment {
+            HorizontalAlignment::Start => x,
+            HorizontalAlignment::Middle => x - width / 2.0,
+            HorizontalAlignment::End => x - width,
+        };
+        Self::new(left, y - height, width, height)
+    }
+
+    pub fn overlaps(&self, other: &LabelBox) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    pub fn shifted(&self, dy: f64) -> Self {
+        Self {
+            y: self.y + dy,
+            ..*self
+        }
+    }
+}
+
+/// How to resolve labels which would overlap one another
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LabelLayout {
+    /// Draw every label at its anchor point, regardless of overlaps
+    #[default]
+    Overlap,
+    /// Move overlapping labels up by at most `max_shift` pixels, drawing a leader line back
+    /// to the anchor point. Labels which still can't be placed are dropped.
+    Nudge { max_shift: f64 },
+    /// Drop labels which overlap one with a higher priority
+    Drop,
+}
+
+impl LabelLayout {
+    pub fn nudge(max_shift: f64) -> Self {
+        Self::Nudge { max_shift }
+    }
+
+    /// Find the vertical offset to draw each label in `boxes` at, or `None` if the label
+    /// should be dropped.
+    ///
+    /// Labels are placed in order of decreasing `priority`, avoiding each other and the
+    /// `occupied` boxes. The boxes of placed labels are added to `occupied`.
+    pub fn place(
+        &self,
+        boxes: &[LabelBox],
+        priority: &[f64],
+        occupied: &mut Vec<LabelBox>,
+    ) -> Vec<Option<f64>> {
+        if *self == LabelLayout::Overlap {
+            return vec![Some(0.0); boxes.len()];
+        }
+        let mut order: Vec<usize> = (0..boxes.len()).collect();
+        order.sort_by(|a, b| priority[*b].total_cmp(&priority[*a]));
+        let mut offsets = vec![None; boxes.len()];
+        for i in order {
+            let label = boxes[i];
+            let max_shift = match self {
+                LabelLayout::Nudge { max_shift } => *max_shift,
+                _ => 0.0,
+            };
+            let step = (label.height / 4.0).max(1.0);
+            let mut shift = 0.0;
+            while shift <= max_shift && label.y - shift >= 0.0 {
+                let candidate = label.shifted(-shift);
+                if !occupied.iter().any(|o| o.overlaps(&candidate)) {
+                    occupied.push(candidate);
+                    offsets[i] = Some(-shift);
+                    break;
+                }
+                shift += step;
+            }
+        }
+        offsets
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mzpeaks::DeconvolutedPeak;
+
+    #[test]
+    fn test_deconvoluted_labels() {
+        let peaks = [
+            DeconvolutedPeak::new(1000.0, 50.0, 2, 0),
+            DeconvolutedPeak::new(1500.0, 500.0, 3, 1),
+            DeconvolutedPeak::new(2000.0, 200.0, 1, 2),
+            DeconvolutedPeak::new(5000.0, 900.0, 1, 3),
+        ];
+        let labels = DeconvolutedLabels::new(2);
+        let series = labels.to_series(peaks.iter(), &CoordinateRange::new(200.0, 2100.0));
+        let texts: Vec<_> = series.points.iter().map(|(_, _, t)| t.as_str()).collect();
+        assert_eq!(texts, ["1500.0000 (z=3)", "2000.0000 (z=1)"]);
+
+        let labels = labels.fields(DeconvolutedLabelFields::Charge);
+        assert_eq!(labels.label(&peaks[0]), "z=2");
+        let labels = labels
+            .fields(DeconvolutedLabelFields::NeutralMass)
+            .mass_precision(1);
+        assert_eq!(labels.label(&peaks[0]), "1000.0");
+    }
+
+    #[test]
+    fn test_peak_labels() {
+        let mut canvas: Canvas<f64, f32> = Canvas::new(1000, 200);
+        canvas.update_scales(
+            CoordinateRange::new(0.0, 1000.0),
+            CoordinateRange::new(100.0, 0.0),
+        );
+        let points = [
+            (100.0, 10.0),
+            (110.0, 90.0),
+            (115.0, 50.0),
+            (500.0, 40.0),
+            (800.0, 5.0),
+            (1200.0, 100.0),
+        ];
+
+        let labels = PeakLabels::top_n(3);
+        let selected = labels.select(points.iter().copied(), &canvas);
+        assert_eq!(selected, [(110.0, 90.0), (500.0, 40.0), (800.0, 5.0)]);
+
+        let labels = PeakLabels::min_intensity(20.0).min_spacing(0.0);
+        let selected = labels.select(points.iter().copied(), &canvas);
+        assert_eq!(selected, [(110.0, 90.0), (115.0, 50.0), (500.0, 40.0)]);
+
+        // An intensity which can't be ranked doesn't stop the rest from being thinned
+        let thinned = thin_by_spacing(
+            vec![(100.0, f32::NAN), (102.0, 10.0), (300.0, 5.0)],
+            &canvas,
+            10.0,
+        );
+        assert_eq!(thinned.len(), 2);
+
+        let series = PeakLabels::top_n(1)
+            .format(PeakLabelFormat::custom(|mz| format!("{mz:.1} m/z")))
+            .to_series(points.iter().copied(), &canvas);
+        assert_eq!(series.points[0].2, "110.0 m/z");
+        assert_eq!(PeakLabels::default().format.format(110.0), "110.0000");
+    }
+
+    #[test]
+    fn test_label_layout() {
+        let boxes = [
+            LabelBox::new(0.0, 90.0, 40.0, 10.0),
+            LabelBox::new(20.0, 92.0, 40.0, 10.0),
+            LabelBox::new(100.0, 90.0, 40.0, 10.0),
+        ];
+        let priority = [1.0, 2.0, 3.0];
+
+        let offsets = LabelLayout::Drop.place(&boxes, &priority, &mut Vec::new());
+        assert_eq!(offsets, [None, Some(0.0), Some(0.0)]);
+
+        let mut occupied = Vec::new();
+        let offsets = LabelLayout::nudge(50.0).place(&boxes, &priority, &mut occupied);
+        assert_eq!(offsets[1], Some(0.0));
+        let shift = offsets[0].unwrap();
+        assert!(shift < 0.0 && shift > -20.0);
+        assert!(!occupied[2].overlaps(&occupied[1]));
+
+        let offsets = LabelLayout::nudge(5.0).place(&boxes[..2], &priority, &mut Vec::new());
+        assert_eq!(offsets, [None, Some(0.0)]);
+
+        let offsets = LabelLayout::Overlap.place(&boxes, &priority, &mut Vec::new());
+        assert!(offsets.iter().all(|o| *o == Some(0.0)));
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::Debug;
+
+#[cfg(any(feature = "png", feature = "pdf"))]
+use std::sync::{Arc, OnceLock};
+
+#[cfg(feature = "png")]
+use resvg::usvg;
+#[cfg(all(feature = "pdf", not(feature = "png")))]
+use svg2pdf::usvg;
+
+/// The height of a line of text relative to its font size
+pub const LINE_HEIGHT: f64 = 1.2;
+
+/// The font size in pixels that `em` sizes are relative to in an unstyled document
+pub const DEFAULT_FONT_SIZE: f64 = 16.0;
+
+/// Measure how much space a piece of text will take up when rendered
+pub trait TextMetrics: Debug {
+    /// The horizontal advance of `text` at `font_size`
+    fn text_width(&self, text: &str, font_size: f64) -> f64;
+
+    /// The height of a single line of text at `font_size`
+    fn text_height(&self, font_size: f64) -> f64 {
+        font_size * LINE_HEIGHT
+    }
+}
+
+/// Estimate text extents from the average glyph widths of a typical proportional font
+/// without loading any fonts.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ApproximateTextMetrics;
+
+impl ApproximateTextMetrics {
+    /// The advance of `c` as a fraction of the font size
+    fn char_width(c: char) -> f64 {
+        match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' => 0.278,
+            ' ' | 'f' | 't' | 'r' | 'I' | '(' | ')' | '[' | ']' | '-' => 0.333,
+            'm' | 'w' | 'M' | 'W' | '%' => 0.833,
+            'A'..='Z' => 0.667,
+            _ => 0.556,
+        }
+    }
+}
+
+impl TextMetrics for ApproximateTextMetrics {
+    fn text_width(&self, text: &str, font_size: f64) -> f64 {
+        text.chars().map(Self::char_width).sum::<f64>() * font_size
+    }
+}
+
+/// Load the system fonts once, using the same generic family mapping as the
+/// rasterized outputs.
+#[cfg(any(feature = "png", feature = "pdf"))]
+pub(crate) fn system_font_database() -> Arc<fontdb::Database> {
+    static DATABASE: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+    DATABASE
+        .get_or_init(|| {
+            let mut fontdb = fontdb::Database::new();
+            fontdb.load_system_fonts();
+
+            fontdb.set_serif_family("Times New Roman");
+            fontdb.set_sans_serif_family("Arial");
+            fontdb.set_cursive_family("Comic Sans MS");
+            fontdb.set_fantasy_family("Impact");
+            fontdb.set_monospace_family("Courier New");
+            Arc::new(fontdb)
+        })
+        .clone()
+}
+
+/// Measure text by laying it out with the fonts that will be used to render it.
+///
+/// Falls back to [`ApproximateTextMetrics`] if no font could be found for the text.
+#[cfg(any(feature = "png", feature = "pdf"))]
+#[derive(Debug, Clone)]
+pub struct FontTextMetrics {
+    database: Arc<fontdb::Database>,
+}
+
+#[cfg(any(feature = "png", feature = "pdf"))]
+impl FontTextMetrics {
+    pub fn new(database: Arc<fontdb::Database>) -> Self {
+        Self { database }
+    }
+
+    pub fn system() -> Self {
+        Self::new(system_font_database())
+    }
+}
+
+#[cfg(any(feature = "png", feature = "pdf"))]
+impl TextMetrics for FontTextMetrics {
+    fn text_width(&self, text: &str, font_size: f64) -> f64 {
+        if text.trim().is_empty() {
+            return ApproximateTextMetrics.text_width(text, font_size);
+        }
+        let escaped = text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        let document = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><text font-size="{font_size}">{escaped}</text></svg>"#
+        );
+        let opts = usvg::Options {
+            fontdb: self.database.clone(),
+            ..Default::default()
+        };
+        match usvg::Tree::from_str(&document, &opts) {
+            Ok(tree) if tree.root().bounding_box().width() > 0.0 => {
+                tree.root().bounding_box().width() as f64
+            }
+            _ => ApproximateTextMetrics.text_width(text, font_size),
+        }
+    }
+}
+
+/// The text metrics used for layout: real font metrics when a rasterizing feature is
+/// enabled, otherwise [`ApproximateTextMetrics`].
+pub fn default_text_metrics() -> &'static dyn TextMetrics {
+    #[cfg(any(feature = "png", feature = "pdf"))]
+    {
+        static METRICS: OnceLock<FontTextMetrics> = OnceLock::new();
+        METRICS.get_or_init(FontTextMetrics::system)
+    }
+    #[cfg(not(any(feature = "png", feature = "pdf")))]
+    {
+        &ApproximateTextMetrics
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_approximate_metrics() {
+        let metrics = ApproximateTextMetrics;
+        let short = metrics.text_width("100", 10.0);
+        let long = metrics.text_width("1.00e10", 10.0);
+        assert!((short - 16.68).abs() < 1e-6);
+        assert!(long > short);
+        assert_eq!(metrics.text_width("100", 20.0), short * 2.0);
+        assert_eq!(metrics.text_height(10.0), 12.0);
+
+        let measured = default_text_metrics().text_width("1.00e10", 10.0);
+        assert!(measured > 0.0);
+    }
+}
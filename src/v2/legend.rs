@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use svg::node::element::{Circle, Group, Line, Rectangle};
+
+use super::chart_regions::{HorizontalAlignment, Sides, TextProps};
+use super::layout::{default_text_metrics, TextMetrics, DEFAULT_FONT_SIZE};
+use super::series::SeriesDescription;
+
+/// The space between the legend's border and its contents, and between the legend and the
+/// edge of the data area
+const LEGEND_PADDING: f64 = 6.0;
+const SWATCH_GAP: f64 = 5.0;
+/// The space between entries of a legend laid out in a row
+const ENTRY_GAP: f64 = 12.0;
+const MARKER_RADIUS: f64 = 4.0;
+
+/// How a [`LegendEntry`]'s swatch is drawn, matching how its series is drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LegendSwatch {
+    #[default]
+    Line,
+    /// A filled box, for filled traces
+    Area,
+    /// A circle, for scatter plots
+    Marker,
+    /// Left out of legends, for series like text annotations
+    Hidden,
+}
+
+/// Where a [`Legend`] is drawn relative to the data area
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LegendPlacement {
+    #[default]
+    TopRight,
+    TopLeft,
+    BottomRight,
+    BottomLeft,
+    /// To the right of the axes
+    OutsideRight,
+    /// Beneath the axes, with the entries laid out in a row
+    Below,
+}
+
+impl LegendPlacement {
+    pub fn is_outside(&self) -> bool {
+        matches!(self, Self::OutsideRight | Self::Below)
+    }
+}
+
+/// A row of a [`Legend`], drawn as a swatch followed by its label
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub label: String,
+    pub color: String,
+    /// The `stroke-dasharray` of the swatch, if it is dashed
+    pub dash: Option<String>,
+    pub swatch: LegendSwatch,
+    /// The name used to exclude or rename this entry, the series id for entries made from a
+    /// [`SeriesDescription`]
+    pub key: String,
+    /// The type of series this entry was made from, if any
+    pub series_type: Option<String>,
+}
+
+impl LegendEntry {
+    pub fn new(label: impl ToString, color: impl ToString) -> Self {
+        let label = label.to_string();
+        Self {
+            key: label.clone(),
+            label,
+            color: color.to_string(),
+            dash: None,
+            swatch: LegendSwatch::default(),
+            series_type: None,
+        }
+    }
+
+    pub fn from_series(description: &SeriesDescription) -> Self {
+        Self {
+            key: description.id(),
+            series_type: Some(description.series_type()),
+            swatch: description.swatch,
+            ..Self::new(&description.label, &description.color)
+        }
+    }
+
+    pub fn dash(mut self, dash: impl ToString) -> Self {
+        self.dash = Some(dash.to_string());
+        self
+    }
+
+    pub fn swatch(mut self, swatch: LegendSwatch) -> Self {
+        self.swatch = swatch;
+        self
+    }
+
+    /// Whether `name` refers to this entry by its key, its label or its series type
+    pub fn is_named(&self, name: &str) -> bool {
+        self.key == name || self.label == name || self.series_type.as_deref() == Some(name)
+    }
+}
+
+/// A key relating the colors of the series in a chart to their labels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Legend {
+    pub entries: Vec<LegendEntry>,
+    pub text_props: TextProps,
+    /// The width of each entry's swatch in pixels
+    pub swatch_width: f64,
+    pub placement: LegendPlacement,
+    /// The names of entries to leave out, matched by [`LegendEntry::is_named`]
+    pub excluded: Vec<String>,
+    /// The names of entries to relabel and their new labels
+    pub renamed: Vec<(String, String)>,
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            text_props: TextProps {
+                text_size: 0.8,
+                horizontal_alignment: HorizontalAlignment::Start,
+                ..Default::default()
+            },
+            swatch_width: 20.0,
+            placement: LegendPlacement::default(),
+            excluded: Vec::new(),
+            renamed: Vec::new(),
+        }
+    }
+}
+
+impl Legend {
+    pub fn new(entries: Vec<LegendEntry>) -> Self {
+        Self {
+            entries,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, entry: LegendEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn placement(mut self, placement: LegendPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Leave out the entries referred to by `name`, which may be a series id like
+    /// `centroid-1`, a series type like `centroid` or an entry's label
+    pub fn exclude(mut self, name: impl ToString) -> Self {
+        let name = name.to_string();
+        if !self.excluded.contains(&name) {
+            self.excluded.push(name);
+        }
+        self
+    }
+
+    /// Relabel the entries referred to by `name`, matched as in [`Legend::exclude`]
+    pub fn rename(mut self, name: impl ToString, label: impl ToString) -> Self {
+        self.renamed.push((name.to_string(), label.to_string()));
+        self
+    }
+
+    /// Add an entry for every series in `series`, in the `order` their series types were
+    /// registered in. Series of a type drawn more than once are numbered.
+    pub fn with_series(
+        mut self,
+        series: &HashMap<String, Vec<SeriesDescription>>,
+        order: &[String],
+    ) -> Self {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for series_type in order {
+            let Some(descriptions) = series.get(series_type) else {
+                continue;
+            };
+            let i = counts.entry(series_type.as_str()).or_default();
+            let Some(description) = descriptions.get(*i) else {
+                continue;
+            };
+            *i += 1;
+            if description.swatch == LegendSwatch::Hidden {
+                continue;
+            }
+            let mut description = description.clone();
+            description.tag = i.to_string();
+            let mut entry = LegendEntry::from_series(&description);
+            if descriptions.len() > 1 {
+                entry.label = format!("{} {}", description.label, description.tag);
+            }
+            self.entries.push(entry);
+        }
+        self
+    }
+
+    /// The entries to draw, after exclusion and renaming
+    pub fn visible_entries(&self) -> Vec<LegendEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| !self.excluded.iter().any(|name| entry.is_named(name)))
+            .map(|entry| {
+                let mut entry = entry.clone();
+                if let Some((_, label)) = self.renamed.iter().find(|(name, _)| entry.is_named(name))
+                {
+                    entry.label = label.clone();
+                }
+                entry
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.visible_entries().is_empty()
+    }
+
+    fn font_size(&self) -> f64 {
+        self.text_props.text_size * DEFAULT_FONT_SIZE
+    }
+
+    fn is_horizontal(&self) -> bool {
+        self.placement == LegendPlacement::Below
+    }
+
+    /// The width of each entry's swatch and label
+    fn entry_widths(&self, entries: &[LegendEntry], metrics: &dyn TextMetrics) -> Vec<f64> {
+        let font_size = self.font_size();
+        entries
+            .iter()
+            .map(|e| self.swatch_width + SWATCH_GAP + metrics.text_width(&e.label, font_size))
+            .collect()
+    }
+
+    /// The width and height of the legend's box in pixels
+    pub fn size(&self, metrics: &dyn TextMetrics) -> (f64, f64) {
+        let entries = self.visible_entries();
+        let widths = self.entry_widths(&entries, metrics);
+        let row_height = metrics.text_height(self.font_size());
+        if self.is_horizontal() {
+            let gaps = ENTRY_GAP * entries.len().saturating_sub(1) as f64;
+            (
+                widths.iter().sum::<f64>() + gaps + 2.0 * LEGEND_PADDING,
+                row_height + 2.0 * LEGEND_PADDING,
+            )
+        } else {
+            (
+                widths.iter().copied().fold(0.0, f64::max) + 2.0 * LEGEND_PADDING,
+                row_height * entries.len() as f64 + 2.0 * LEGEND_PADDING,
+            )
+        }
+    }
+
+    /// The space this legend needs beyond the axes, when it is drawn outside of them
+    pub fn extent(&self) -> Sides {
+        let (width, height) = self.size(default_text_metrics());
+        let mut extent = Sides::default();
+        match self.placement {
+            LegendPlacement::OutsideRight => extent.right = width + LEGEND_PADDING,
+            LegendPlacement::Below => extent.bottom = height + LEGEND_PADDING,
+            _ => {}
+        }
+        extent
+    }
+
+    fn swatch_svg(&self, entry: &LegendEntry, x: f64, y: f64, row_height: f64) -> Group {
+        let group = Group::new();
+        match entry.swatch {
+            LegendSwatch::Area => group.add(
+                Rectangle::new()
+                    .set("x", x)
+                    .set("y", y - row_height * 0.3)
+                    .set("width", self.swatch_width)
+                    .set("height", row_height * 0.6)
+                    .set("fill", entry.color.clone())
+                    .set("fill-opacity", "75%")
+                    .set("stroke", "black")
+                    .set("stroke-width", 1),
+            ),
+            LegendSwatch::Marker => group.add(
+                Circle::new()
+                    .set("cx", x + self.swatch_width / 2.0)
+                    .set("cy", y)
+                    .set("r", MARKER_RADIUS)
+                    .set("fill", entry.color.clone())
+                    .set("stroke", "black"),
+            ),
+            LegendSwatch::Line | LegendSwatch::Hidden => {
+                let mut line = Line::new()
+                    .set("x1", x)
+                    .set("x2", x + self.swatch_width)
+                    .set("y1", y)
+                    .set("y2", y)
+                    .set("stroke", entry.color.clone())
+                    .set("stroke-width", 2);
+                if let Some(dash) = entry.dash.as_ref() {
+                    line = line.set("stroke-dasharray", dash.clone());
+                }
+                group.add(line)
+            }
+        }
+    }
+
+    /// Draw the legend in the position given by [`Legend::placement`] for a data area
+    /// `width` × `height` pixels surrounded by axes taking up `axes`. The legend is drawn in
+    /// the coordinates of the data area.
+    pub fn to_svg(&self, width: f64, height: f64, axes: &Sides) -> Group {
+        let metrics = default_text_metrics();
+        let entries = self.visible_entries();
+        let widths = self.entry_widths(&entries, metrics);
+        let (box_width, box_height) = self.size(metrics);
+        let row_height = metrics.text_height(self.font_size());
+        let (x, y) = match self.placement {
+            LegendPlacement::TopRight => (width - box_width - LEGEND_PADDING, LEGEND_PADDING),
+            LegendPlacement::TopLeft => (LEGEND_PADDING, LEGEND_PADDING),
+            LegendPlacement::BottomRight => (
+                width - box_width - LEGEND_PADDING,
+                height - box_height - LEGEND_PADDING,
+            ),
+            LegendPlacement::BottomLeft => (LEGEND_PADDING, height - box_height - LEGEND_PADDING),
+            LegendPlacement::OutsideRight => (width + axes.right, 0.0),
+            LegendPlacement::Below => (0.0, height + axes.bottom),
+        };
+        let mut group = Group::new()
+            .set("class", "legend")
+            .set("transform", format!("translate({x}, {y})"))
+            .add(
+                Rectangle::new()
+                    .set("width", box_width)
+                    .set("height", box_height)
+                    .set("fill", "white")
+                    .set("fill-opacity", 0.8)
+                    .set("stroke", "lightgrey")
+                    .set("stroke-width", 0.75),
+            );
+        let mut entry_x = LEGEND_PADDING;
+        for (i, (entry, entry_width)) in entries.iter().zip(widths).enumerate() {
+            let (x, y) = if self.is_horizontal() {
+                (entry_x, LEGEND_PADDING + row_height * 0.5)
+            } else {
+                (
+                    LEGEND_PADDING,
+                    LEGEND_PADDING + row_height * (i as f64 + 0.5),
+                )
+            };
+            entry_x += entry_width + ENTRY_GAP;
+            let label = self
+                .text_props
+                .text(entry.label.clone())
+                .set("x", x + self.swatch_width + SWATCH_GAP)
+                .set("y", y)
+                .set("dy", "0.32em");
+            group = group.add(
+                Group::new()
+                    .set("class", "legend-entry")
+                    .add(self.swatch_svg(entry, x, y, row_height))
+                    .add(label),
+            );
+        }
+        group
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_legend_from_series() {
+        let mut series: HashMap<String, Vec<SeriesDescription>> = HashMap::new();
+        let mut profile = SeriesDescription::new("profile".into(), "steelblue".into());
+        profile.tag = "1".into();
+        series.insert("profile".into(), vec![profile]);
+        let mut traces = Vec::new();
+        for tag in ["1", "2"] {
+            let mut trace = SeriesDescription::new("feature".into(), "red".into());
+            trace.swatch = LegendSwatch::Area;
+            trace.tag = tag.into();
+            traces.push(trace);
+        }
+        series.insert("feature".into(), traces);
+        let mut labels = SeriesDescription::from("peak-labels");
+        labels.swatch = LegendSwatch::Hidden;
+        series.insert("peak-labels".into(), vec![labels]);
+
+        let order: Vec<String> = ["profile", "feature", "peak-labels", "feature"]
+            .map(String::from)
+            .to_vec();
+
+        let legend = Legend::default().with_series(&series, &order);
+        let labels: Vec<_> = legend.entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["profile", "feature 1", "feature 2"]);
+
+        let legend = Legend::default()
+            .with_series(&series, &order)
+            .exclude("feature-2")
+            .rename("profile", "Raw signal");
+        let entries = legend.visible_entries();
+        let labels: Vec<_> = entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["Raw signal", "feature 1"]);
+        assert_eq!(entries[1].swatch, LegendSwatch::Area);
+
+        let inside = legend.to_svg(400.0, 300.0, &Sides::uniform(20.0));
+        assert!(inside.to_string().contains("<rect"));
+        assert_eq!(legend.extent(), Sides::default());
+
+        let below = legend.placement(LegendPlacement::Below);
+        let (width, height) = below.size(default_text_metrics());
+        assert!(width > height);
+        assert_eq!(below.extent().bottom, height + LEGEND_PADDING);
+    }
+}
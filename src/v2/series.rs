@@ -1,17 +1,22 @@
 use std::marker::PhantomData;
 
-use mzdata::spectrum::{Precursor, PrecursorSelection};
+use mzdata::spectrum::{ChromatogramType, Precursor, PrecursorSelection};
 use mzpeaks::{
     feature::{ChargedFeature, Feature, FeatureLike, SimpleFeature},
     peak_set::PeakSetVec,
-    CentroidLike, CentroidPeak, DeconvolutedCentroidLike, DeconvolutedPeak,
-    IntensityMeasurement, MZLocated, MZPeakSetType, MassPeakSetType,
+    CentroidLike, CentroidPeak, DeconvolutedCentroidLike, DeconvolutedPeak, IntensityMeasurement,
+    MZLocated, MZPeakSetType, MassPeakSetType, PeakCollection, Time, Tolerance, MZ,
 };
 use num_traits::Float;
 
-use svg::node::element::{path::Data as PathData, Circle, Group, Path, Polyline};
+use svg::node::element::{path::Data as PathData, Circle, Group, Line, Path, Polyline};
 
 use super::chart_regions::{Canvas, RenderCoordinate, TextProps};
+use super::labels::{LabelBox, LabelLayout, PeakLabels};
+use super::layout::default_text_metrics;
+use super::legend::LegendSwatch;
+use crate::isotopes::IsotopeModel;
+use crate::CoordinateRange;
 
 pub const DEFAULT_COLOR_CYCLE: &'static [&'static str] = &[
     "black",
@@ -80,8 +85,57 @@ pub trait PlotSeries<X: RenderCoordinate, Y: RenderCoordinate> {
 
     fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group;
 
+    /// Render the series like [`PlotSeries::to_svg`], keeping any labels it draws clear of
+    /// the `placed` labels of earlier series and adding their boxes to `placed`.
+    ///
+    /// The default implementation draws no labels.
+    fn to_svg_with_labels(&self, canvas: &Canvas<X, Y>, _placed: &mut Vec<LabelBox>) -> Group {
+        self.to_svg(canvas)
+    }
+
     fn slice_x(&mut self, start: X, end: X);
     fn slice_y(&mut self, start: Y, end: Y);
+
+    /// Restrict the series to the union of the `segments` of a broken horizontal axis.
+    ///
+    /// The default implementation keeps everything between the first and last segment,
+    /// relying on [`PlotSeries::to_svg`] to skip values which fall between segments.
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        let start = segments.iter().map(|s| s.min()).reduce(|a, b| a.min(b));
+        let end = segments.iter().map(|s| s.max()).reduce(|a, b| a.max(b));
+        if let (Some(start), Some(end)) = (start, end) {
+            self.slice_x(start, end);
+        }
+    }
+}
+
+/// Test whether `x` falls within any of `segments`
+pub(crate) fn within_segments<X: RenderCoordinate>(segments: &[CoordinateRange<X>], x: X) -> bool {
+    segments.iter().any(|s| s.contains(x))
+}
+
+/// Split `points` into runs of consecutive points which fall within the same segment of
+/// `canvas`'s horizontal axis, omitting points which fall between segments. An unbroken
+/// axis produces a single run.
+fn segment_runs<X: RenderCoordinate, Y: RenderCoordinate>(
+    canvas: &Canvas<X, Y>,
+    points: &[(X, Y)],
+) -> Vec<Vec<(X, Y)>> {
+    let mut runs: Vec<Vec<(X, Y)>> = Vec::new();
+    let mut current_segment = None;
+    for (x, y) in points.iter().copied() {
+        let segment = canvas.x_scale().segment_of(x);
+        if segment.is_none() {
+            current_segment = None;
+            continue;
+        }
+        if segment != current_segment || runs.is_empty() {
+            runs.push(Vec::new());
+            current_segment = segment;
+        }
+        runs.last_mut().unwrap().push((x, y));
+    }
+    runs
 }
 
 pub trait AsSeries<X: RenderCoordinate, Y: RenderCoordinate> {
@@ -103,6 +157,8 @@ pub struct SeriesDescription {
     pub label: String,
     pub color: String,
     pub tag: String,
+    /// How this series is shown in a [`Legend`](super::Legend)
+    pub swatch: LegendSwatch,
 }
 
 impl SeriesDescription {
@@ -111,6 +167,7 @@ impl SeriesDescription {
             label,
             color,
             tag: String::new(),
+            swatch: LegendSwatch::default(),
         }
     }
 
@@ -119,6 +176,11 @@ impl SeriesDescription {
         self
     }
 
+    pub fn with_swatch(mut self, swatch: LegendSwatch) -> Self {
+        self.swatch = swatch;
+        self
+    }
+
     pub fn series_type(&self) -> String {
         self.label.to_string()
     }
@@ -166,28 +228,26 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> LineSeries<X, Y> {
     }
 
     pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
-        let path_data: Vec<_> = self
-            .points
-            .iter()
-            .enumerate()
-            .map(|(_, (mz, inten))| {
-                format!(
-                    "{},{}",
-                    canvas.x_axis.scale.transform(*mz).to_f64().unwrap(),
-                    canvas.y_axis.scale.transform(*inten).to_f64().unwrap(),
-                )
+        let group = segment_runs(canvas, &self.points)
+            .into_iter()
+            .map(|run| {
+                let path_data: Vec<_> = run
+                    .iter()
+                    .map(|(mz, inten)| {
+                        let (x, y) = canvas.transform(*mz, *inten);
+                        format!("{},{}", x, y)
+                    })
+                    .collect();
+                let points = path_data.join(" ");
+
+                Polyline::new()
+                    .set("points", points)
+                    .set("fill", "none")
+                    .set("stroke", self.description.color.clone())
+                    .set("stroke-width", 1)
             })
-            .collect();
-        let points = path_data.join(" ");
-
-        let path = Polyline::new()
-            .set("points", points)
-            .set("fill", "none")
-            .set("stroke", self.description.color.clone())
-            .set("stroke-width", 1);
-        let group = Group::new();
+            .fold(Group::new(), |group, path| group.add(path));
         group
-            .add(path)
             .set("class", self.description.label.clone())
             .set("id", self.description.id())
     }
@@ -216,6 +276,10 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for LineSeries<X
         self.points = points;
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points.retain(|(x, _)| within_segments(segments, *x));
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         let points = self
             .points
@@ -227,6 +291,87 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for LineSeries<X
     }
 }
 
+/// Draw the area beneath a line with an opaque fill, so series drawn afterwards hide the
+/// parts of earlier series behind them
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AreaSeries<X: RenderCoordinate, Y: RenderCoordinate> {
+    pub points: Vec<(X, Y)>,
+    pub description: SeriesDescription,
+    pub fill: String,
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> AreaSeries<X, Y> {
+    pub fn new(points: Vec<(X, Y)>, description: SeriesDescription) -> Self {
+        Self {
+            points,
+            description: description.with_swatch(LegendSwatch::Area),
+            fill: "white".to_string(),
+        }
+    }
+
+    pub fn fill(mut self, fill: impl ToString) -> Self {
+        self.fill = fill.to_string();
+        self
+    }
+
+    pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        let baseline = canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap();
+        segment_runs(canvas, &self.points)
+            .into_iter()
+            .filter(|run| !run.is_empty())
+            .map(|run| {
+                let first = canvas.x_scale().transform(run[0].0).to_f64().unwrap();
+                let last = canvas
+                    .x_scale()
+                    .transform(run[run.len() - 1].0)
+                    .to_f64()
+                    .unwrap();
+                let path_data = run
+                    .iter()
+                    .fold(
+                        PathData::new().move_to((first, baseline)),
+                        |state, (x, y)| state.line_to(canvas.transform(*x, *y)),
+                    )
+                    .line_to((last, baseline))
+                    .close();
+                Path::new()
+                    .set("d", path_data)
+                    .set("fill", self.fill.clone())
+                    .set("stroke", self.description.color.clone())
+                    .set("stroke-width", 1)
+            })
+            .fold(Group::new(), |group, path| group.add(path))
+            .set("class", self.description.label.clone())
+            .set("id", self.description.id())
+    }
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for AreaSeries<X, Y> {
+    fn description(&self) -> &SeriesDescription {
+        &self.description
+    }
+
+    fn description_mut(&mut self) -> &mut SeriesDescription {
+        &mut self.description
+    }
+
+    fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        self.to_svg(canvas)
+    }
+
+    fn slice_x(&mut self, start: X, end: X) {
+        self.points.retain(|(x, _)| (x >= &start) && (x <= &end));
+    }
+
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points.retain(|(x, _)| within_segments(segments, *x));
+    }
+
+    fn slice_y(&mut self, start: Y, end: Y) {
+        self.points.retain(|(_, y)| (y >= &start) && (y <= &end));
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct ContinuousSeries<X: RenderCoordinate, Y: RenderCoordinate> {
     pub points: Vec<(X, Y)>,
@@ -253,25 +398,23 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> ContinuousSeries<X, Y> {
     }
 
     pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
-        let min_mz = self
-            .points
-            .iter()
-            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
-            .copied()
-            .unwrap_or((X::zero(), Y::zero()))
-            .0;
         let path_data =
-            self.points
-                .iter()
-                .enumerate()
-                .fold(PathData::new(), |mut state, (i, (mz, inten))| {
-                    if i == 0 {
-                        state = state.move_to((
-                            canvas.x_axis.scale.transform(min_mz).to_f64().unwrap(),
-                            canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
-                        ));
-                    }
-                    state.line_to(canvas.transform(*mz, *inten))
+            segment_runs(canvas, &self.points)
+                .into_iter()
+                .fold(PathData::new(), |state, run| {
+                    let min_mz = run
+                        .iter()
+                        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                        .copied()
+                        .unwrap_or((X::zero(), Y::zero()))
+                        .0;
+                    let state = state.move_to((
+                        canvas.x_scale().transform(min_mz).to_f64().unwrap(),
+                        canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
+                    ));
+                    run.iter().fold(state, |state, (mz, inten)| {
+                        state.line_to(canvas.transform(*mz, *inten))
+                    })
                 });
         let path = Path::new().set("fill", "none").set("d", path_data);
         let group = Group::new();
@@ -306,6 +449,10 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for ContinuousSe
         self.points = points;
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points.retain(|(x, _)| within_segments(segments, *x));
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         let points = self
             .points
@@ -322,6 +469,8 @@ pub struct AnnotationSeries<X: RenderCoordinate, Y: RenderCoordinate> {
     pub points: Vec<(X, Y, String)>,
     pub description: SeriesDescription,
     pub text_props: TextProps,
+    /// How to resolve overlapping labels, prioritizing those with higher y values
+    pub layout: LabelLayout,
 }
 
 impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for AnnotationSeries<X, Y> {
@@ -335,6 +484,10 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for AnnotationSe
         self.to_svg(canvas)
     }
 
+    fn to_svg_with_labels(&self, canvas: &Canvas<X, Y>, placed: &mut Vec<LabelBox>) -> Group {
+        self.to_svg_with_labels(canvas, placed)
+    }
+
     fn slice_x(&mut self, start: X, end: X) {
         let points = self
             .points
@@ -345,6 +498,10 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for AnnotationSe
         self.points = points;
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points.retain(|(x, _, _)| within_segments(segments, *x));
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         let points = self
             .points
@@ -364,8 +521,9 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> AnnotationSeries<X, Y> {
     ) -> Self {
         Self {
             points,
-            description,
+            description: description.with_swatch(LegendSwatch::Hidden),
             text_props,
+            layout: LabelLayout::default(),
         }
     }
 
@@ -382,14 +540,32 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> AnnotationSeries<X, Y> {
                 .zip(text_iter)
                 .map(|((x, y), text)| (x, y, text))
                 .collect(),
-            description,
+            description: description.with_swatch(LegendSwatch::Hidden),
             text_props,
+            layout: LabelLayout::default(),
         }
     }
 
+    pub fn layout(mut self, layout: LabelLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
     pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        self.to_svg_with_labels(canvas, &mut Vec::new())
+    }
+
+    /// Render the labels, keeping them clear of the `placed` labels and adding their boxes
+    /// to `placed` when [`AnnotationSeries::layout`] avoids overlaps
+    pub fn to_svg_with_labels(&self, canvas: &Canvas<X, Y>, placed: &mut Vec<LabelBox>) -> Group {
+        if self.layout != LabelLayout::Overlap {
+            return self.to_svg_with_layout(canvas, placed);
+        }
         let mut group = Group::new();
         for (x, y, text) in self.points.iter() {
+            if canvas.x_scale().segment_of(*x).is_none() {
+                continue;
+            }
             let (x2, y2) = canvas.transform(*x, *y);
             group = group.add(
                 Group::new()
@@ -399,6 +575,45 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> AnnotationSeries<X, Y> {
         }
         group
     }
+
+    fn to_svg_with_layout(&self, canvas: &Canvas<X, Y>, placed: &mut Vec<LabelBox>) -> Group {
+        let metrics = default_text_metrics();
+        let visible: Vec<_> = self
+            .points
+            .iter()
+            .filter(|(x, _, _)| canvas.x_scale().segment_of(*x).is_some())
+            .map(|(x, y, text)| {
+                let (x2, y2) = canvas.transform(*x, *y);
+                let bounds = LabelBox::for_text(text, x2, y2, &self.text_props, metrics);
+                (x2, y2, text, bounds, y.to_f64().unwrap())
+            })
+            .collect();
+        let boxes: Vec<_> = visible.iter().map(|(_, _, _, b, _)| *b).collect();
+        let priority: Vec<_> = visible.iter().map(|(_, _, _, _, p)| *p).collect();
+        let offsets = self.layout.place(&boxes, &priority, placed);
+
+        let mut group = Group::new();
+        for ((x2, y2, text, _, _), offset) in visible.into_iter().zip(offsets) {
+            let Some(offset) = offset else { continue };
+            let mut label = Group::new()
+                .set("transform", format!("translate({}, {})", x2, y2 + offset))
+                .add(self.text_props.text(text.clone()));
+            if offset != 0.0 {
+                label = label.add(
+                    Line::new()
+                        .set("x1", 0)
+                        .set("y1", 0)
+                        .set("x2", 0)
+                        .set("y2", -offset)
+                        .set("stroke", "grey")
+                        .set("stroke-width", "0.5pt")
+                        .set("class", "leader-line"),
+                );
+            }
+            group = group.add(label)
+        }
+        group
+    }
 }
 
 mod mzdata_continuum {
@@ -422,6 +637,38 @@ mod mzdata_continuum {
     }
 }
 
+mod mzdata_chromatogram {
+    use mzdata::spectrum::{Chromatogram, ChromatogramLike};
+
+    use super::*;
+
+    /// The time and intensity arrays of `chromatogram`, empty if either is missing
+    pub fn chromatogram_feature(chromatogram: &Chromatogram) -> SimpleFeature<MZ, Time> {
+        let mut feature = SimpleFeature::empty(0.0);
+        if let (Ok(times), Ok(intensities)) = (chromatogram.time(), chromatogram.intensity()) {
+            for (time, inten) in times.iter().zip(intensities.iter()) {
+                feature.push_raw(0.0, *time, *inten);
+            }
+        }
+        feature
+    }
+
+    impl AsSeries<f64, f32> for Chromatogram {
+        type Series = ChromatogramSeries<f64, f32>;
+
+        fn as_series(&self) -> Self::Series {
+            let chromatogram_type = self.chromatogram_type();
+            ChromatogramSeries::new(
+                chromatogram_feature(self),
+                ChromatogramStyle::for_type(chromatogram_type),
+                chromatogram_label(chromatogram_type).into(),
+            )
+        }
+    }
+}
+
+pub use mzdata_chromatogram::chromatogram_feature;
+
 pub fn peaks_to_arrays<
     'transient,
     'lifespan: 'transient,
@@ -481,6 +728,16 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, T: CentroidLike + Clone + 'static
         self.peaks = points;
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        let points = self
+            .peaks
+            .iter()
+            .filter(|p| within_segments(segments, X::from(p.mz()).unwrap()))
+            .cloned()
+            .collect();
+        self.peaks = points;
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         let points = self
             .peaks
@@ -518,6 +775,20 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, T: CentroidLike + Clone + 'static
         let group = proxy.to_svg(canvas);
         group
     }
+
+    /// Build m/z labels for the peaks of this series selected by `labels`
+    pub fn peak_labels(
+        &self,
+        labels: &PeakLabels,
+        canvas: &Canvas<X, Y>,
+    ) -> AnnotationSeries<X, Y> {
+        labels.to_series(
+            self.peaks
+                .iter()
+                .map(|p| (X::from(p.mz()).unwrap(), Y::from(p.intensity()).unwrap())),
+            canvas,
+        )
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -558,6 +829,16 @@ impl<
         self.peaks = points;
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        let points = self
+            .peaks
+            .iter()
+            .filter(|p| within_segments(segments, X::from(p.mz()).unwrap()))
+            .cloned()
+            .collect();
+        self.peaks = points;
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         let points = self
             .peaks
@@ -602,6 +883,220 @@ impl<
     }
 }
 
+/// How the theoretical peaks of an [`IsotopicEnvelopeSeries`] are drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeStyle {
+    /// An unfilled circle at the apex of each theoretical peak
+    #[default]
+    HollowMarkers,
+    /// A dashed line from the baseline to the apex of each theoretical peak
+    DashedSticks,
+}
+
+/// The theoretical peaks of a single isotopic envelope
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IsotopicEnvelope<X: RenderCoordinate, Y: RenderCoordinate> {
+    pub peaks: Vec<(X, Y)>,
+    /// How well the experimental peaks fit this envelope, if known
+    pub score: Option<f64>,
+}
+
+/// Overlay the theoretical isotopic pattern of deconvoluted peaks on the experimental centroids
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IsotopicEnvelopeSeries<X: RenderCoordinate, Y: RenderCoordinate> {
+    pub envelopes: Vec<IsotopicEnvelope<X, Y>>,
+    pub description: SeriesDescription,
+    pub style: EnvelopeStyle,
+    pub marker_radius: f64,
+    pub text_props: TextProps,
+}
+
+pub const DEFAULT_MAX_ISOTOPIC_PEAKS: usize = 10;
+pub const DEFAULT_MIN_ISOTOPIC_ABUNDANCE: f64 = 0.01;
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> IsotopicEnvelopeSeries<X, Y> {
+    pub fn new(envelopes: Vec<IsotopicEnvelope<X, Y>>, description: SeriesDescription) -> Self {
+        Self {
+            envelopes,
+            description,
+            style: EnvelopeStyle::default(),
+            marker_radius: 3.0,
+            text_props: TextProps {
+                text_size: 0.7,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Expand each deconvoluted peak into its theoretical isotopic pattern under `model`,
+    /// splitting the peak's total intensity between its isotopic peaks.
+    pub fn from_deconvoluted<T: DeconvolutedCentroidLike>(
+        peaks: impl Iterator<Item = T>,
+        model: &IsotopeModel,
+        description: SeriesDescription,
+    ) -> Self {
+        let envelopes = peaks
+            .map(|peak| {
+                let peak = peak.as_centroid();
+                let intensity = peak.intensity as f64;
+                let peaks = model
+                    .isotopic_pattern(
+                        peak.neutral_mass,
+                        peak.charge,
+                        DEFAULT_MAX_ISOTOPIC_PEAKS,
+                        DEFAULT_MIN_ISOTOPIC_ABUNDANCE,
+                    )
+                    .into_iter()
+                    .map(|(mz, abundance)| {
+                        (
+                            X::from(mz).unwrap(),
+                            Y::from(abundance * intensity).unwrap(),
+                        )
+                    })
+                    .collect();
+                IsotopicEnvelope { peaks, score: None }
+            })
+            .collect();
+        Self::new(envelopes, description)
+    }
+
+    /// Score each envelope by the cosine similarity between its theoretical peaks and the most
+    /// intense experimental peak within `tolerance` of each of them.
+    pub fn with_fit_scores<C: CentroidLike>(
+        mut self,
+        experimental: &MZPeakSetType<C>,
+        tolerance: Tolerance,
+    ) -> Self {
+        for envelope in self.envelopes.iter_mut() {
+            let (dot, theoretical_norm, experimental_norm) =
+                envelope
+                    .peaks
+                    .iter()
+                    .fold((0.0, 0.0, 0.0), |(dot, tnorm, enorm), (x, y)| {
+                        let theoretical = y.to_f64().unwrap();
+                        let observed = experimental
+                            .all_peaks_for(x.to_f64().unwrap(), tolerance)
+                            .iter()
+                            .map(|p| p.intensity() as f64)
+                            .fold(0.0, f64::max);
+                        (
+                            dot + theoretical * observed,
+                            tnorm + theoretical.powi(2),
+                            enorm + observed.powi(2),
+                        )
+                    });
+            let denominator = (theoretical_norm * experimental_norm).sqrt();
+            envelope.score = Some(if denominator > 0.0 {
+                dot / denominator
+            } else {
+                0.0
+            });
+        }
+        self
+    }
+
+    pub fn style(mut self, style: EnvelopeStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        let mut group = Group::new()
+            .set("class", self.series_type())
+            .set("id", self.series_id())
+            .set("stroke", self.description.color.clone())
+            .set("fill", "none");
+        for envelope in self.envelopes.iter() {
+            let mut envelope_group = Group::new().set("class", "isotopic-envelope");
+            for (x, y) in envelope.peaks.iter() {
+                if canvas.x_scale().segment_of(*x).is_none() {
+                    continue;
+                }
+                let (px, py) = canvas.transform(*x, *y);
+                envelope_group = match self.style {
+                    EnvelopeStyle::HollowMarkers => envelope_group.add(
+                        Circle::new()
+                            .set("cx", px)
+                            .set("cy", py)
+                            .set("r", self.marker_radius),
+                    ),
+                    EnvelopeStyle::DashedSticks => envelope_group.add(
+                        Line::new()
+                            .set("x1", px)
+                            .set("x2", px)
+                            .set(
+                                "y1",
+                                canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
+                            )
+                            .set("y2", py)
+                            .set("stroke-dasharray", "3 2"),
+                    ),
+                };
+            }
+            let apex = envelope
+                .peaks
+                .iter()
+                .filter(|(x, y)| y.is_finite() && canvas.x_scale().segment_of(*x).is_some())
+                .max_by(|a, b| a.1.to_f64().unwrap().total_cmp(&b.1.to_f64().unwrap()));
+            if let (Some(score), Some((x, y))) = (envelope.score, apex) {
+                let (px, py) = canvas.transform(*x, *y);
+                envelope_group = envelope_group.add(
+                    Group::new()
+                        .set("class", "envelope-score")
+                        .set(
+                            "transform",
+                            format!("translate({}, {})", px, py - self.marker_radius - 4.0),
+                        )
+                        .add(
+                            self.text_props
+                                .text(format!("{score:.2}"))
+                                .set("stroke", "none"),
+                        ),
+                );
+            }
+            group = group.add(envelope_group);
+        }
+        group
+    }
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for IsotopicEnvelopeSeries<X, Y> {
+    fn description(&self) -> &SeriesDescription {
+        &self.description
+    }
+
+    fn description_mut(&mut self) -> &mut SeriesDescription {
+        &mut self.description
+    }
+
+    fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        self.to_svg(canvas)
+    }
+
+    fn slice_x(&mut self, start: X, end: X) {
+        for envelope in self.envelopes.iter_mut() {
+            envelope.peaks.retain(|(x, _)| *x >= start && *x <= end);
+        }
+        self.envelopes.retain(|e| !e.peaks.is_empty());
+    }
+
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        for envelope in self.envelopes.iter_mut() {
+            envelope
+                .peaks
+                .retain(|(x, _)| within_segments(segments, *x));
+        }
+        self.envelopes.retain(|e| !e.peaks.is_empty());
+    }
+
+    fn slice_y(&mut self, start: Y, end: Y) {
+        for envelope in self.envelopes.iter_mut() {
+            envelope.peaks.retain(|(_, y)| *y >= start && *y <= end);
+        }
+        self.envelopes.retain(|e| !e.peaks.is_empty());
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PrecursorSeries<X: RenderCoordinate, Y: RenderCoordinate> {
     mz: X,
@@ -609,6 +1104,7 @@ pub struct PrecursorSeries<X: RenderCoordinate, Y: RenderCoordinate> {
     charge: Option<i32>,
     in_frame: bool,
     description: SeriesDescription,
+    label_layout: LabelLayout,
 }
 
 impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for PrecursorSeries<X, Y> {
@@ -621,8 +1117,12 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for PrecursorSer
     }
 
     fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        self.to_svg_with_labels(canvas, &mut Vec::new())
+    }
+
+    fn to_svg_with_labels(&self, canvas: &Canvas<X, Y>, placed: &mut Vec<LabelBox>) -> Group {
         let root = Group::new();
-        if !self.in_frame {
+        if !self.in_frame || canvas.x_scale().segment_of(self.mz).is_none() {
             return root;
         }
         let x = self.mz;
@@ -631,18 +1131,21 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for PrecursorSer
         let s = format!("{x:0.2}, {z}");
         let pts = vec![(x, y, s)];
 
-        let mut text_props = TextProps::default();
-        text_props.text_size = 0.8;
-        text_props.color = "skyblue".into();
+        let text_props = TextProps {
+            text_size: 0.8,
+            color: "skyblue".into(),
+            ..Default::default()
+        };
 
-        let annot = AnnotationSeries::new(pts, "precursor-label".into(), text_props);
+        let annot = AnnotationSeries::new(pts, "precursor-label".into(), text_props)
+            .layout(self.label_layout);
         let annot_group = annot
-            .to_svg(&canvas)
+            .to_svg_with_labels(canvas, placed)
             .set("stroke", "black")
             .set("stroke-width", "0.1pt");
 
         let line_group = LineSeries::new(vec![(x, Y::zero()), (x, y)], "precursor-line".into())
-            .to_svg(&canvas)
+            .to_svg(canvas)
             .set("stroke-dasharray", 4)
             .set("stroke", self.description.color.clone())
             .set("stroke-width", "0.5pt");
@@ -657,6 +1160,10 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for PrecursorSer
         self.in_frame = start <= self.mz && self.mz <= end;
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.in_frame = within_segments(segments, self.mz);
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         self.intensity = start.max(end);
     }
@@ -670,9 +1177,16 @@ impl<X: RenderCoordinate, Y: RenderCoordinate> PrecursorSeries<X, Y> {
             charge,
             description,
             in_frame: true,
+            label_layout: LabelLayout::default(),
         }
     }
 
+    /// Avoid overlapping the precursor label with other labels using `layout`
+    pub fn label_layout(mut self, layout: LabelLayout) -> Self {
+        self.label_layout = layout;
+        self
+    }
+
     pub fn from_precursor(precursor: &impl PrecursorSelection) -> Self {
         let ion = precursor.ion();
         Self::new(
@@ -714,7 +1228,7 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, C1, C2, F: FeatureLike<C1, C2>>
 
         Self {
             feature,
-            description,
+            description: description.with_swatch(LegendSwatch::Area),
             points,
             _c1: PhantomData,
             _c2: PhantomData,
@@ -724,42 +1238,40 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, C1, C2, F: FeatureLike<C1, C2>>
     }
 
     pub fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
-        let start_time = self
-            .points
-            .iter()
-            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
-            .copied()
-            .unwrap_or((X::zero(), Y::zero()))
-            .0;
-        let end_time = self
-            .points
-            .iter()
-            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
-            .copied()
-            .unwrap_or((X::zero(), Y::zero()))
-            .0;
-        let path_data = self
-            .points
-            .iter()
-            .enumerate()
-            .fold(PathData::new(), |mut state, (i, (time, inten))| {
-                if i == 0 {
-                    state = state.move_to((
-                        canvas.x_axis.scale.transform(start_time).to_f64().unwrap(),
+        let path_data =
+            segment_runs(canvas, &self.points)
+                .into_iter()
+                .fold(PathData::new(), |state, run| {
+                    let start_time = run
+                        .iter()
+                        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                        .copied()
+                        .unwrap_or((X::zero(), Y::zero()))
+                        .0;
+                    let end_time = run
+                        .iter()
+                        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                        .copied()
+                        .unwrap_or((X::zero(), Y::zero()))
+                        .0;
+                    let state = state.move_to((
+                        canvas.x_scale().transform(start_time).to_f64().unwrap(),
                         canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
                     ));
-                }
-                state.line_to(canvas.transform(X::from(*time).unwrap(), Y::from(*inten).unwrap()))
-            })
-            .line_to((
-                canvas.x_axis.scale.transform(end_time).to_f64().unwrap(),
-                canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
-            ))
-            .line_to((
-                canvas.x_axis.scale.transform(start_time).to_f64().unwrap(),
-                canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
-            ))
-            .close();
+                    run.iter()
+                        .fold(state, |state, (time, inten)| {
+                            state.line_to(canvas.transform(*time, *inten))
+                        })
+                        .line_to((
+                            canvas.x_scale().transform(end_time).to_f64().unwrap(),
+                            canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
+                        ))
+                        .line_to((
+                            canvas.x_scale().transform(start_time).to_f64().unwrap(),
+                            canvas.y_axis.scale.transform(Y::zero()).to_f64().unwrap(),
+                        ))
+                        .close()
+                });
         let path = Path::new()
             .set("fill", self.color())
             .set("d", path_data.clone())
@@ -801,6 +1313,10 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, C1, C2, F: FeatureLike<C1, C2>> P
         self.points = points;
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points.retain(|(x, _)| within_segments(segments, *x));
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         let points = self
             .points
@@ -848,6 +1364,121 @@ where
     }
 }
 
+/// How a [`ChromatogramSeries`] is drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChromatogramStyle {
+    #[default]
+    Line,
+    /// A filled trace, like a [`TraceSeries`]
+    Filled,
+}
+
+impl ChromatogramStyle {
+    /// Chromatograms of a single ion or transition are drawn as filled peaks, while whole-run
+    /// and instrument traces are drawn as lines
+    pub fn for_type(chromatogram_type: ChromatogramType) -> Self {
+        match chromatogram_type {
+            ChromatogramType::SelectedIonCurrentChromatogram
+            | ChromatogramType::SelectedIonMonitoringChromatogram
+            | ChromatogramType::SelectedReactionMonitoringChromatogram => Self::Filled,
+            _ => Self::Line,
+        }
+    }
+}
+
+/// The series label used for a chromatogram of `chromatogram_type`
+pub fn chromatogram_label(chromatogram_type: ChromatogramType) -> &'static str {
+    match chromatogram_type {
+        ChromatogramType::TotalIonCurrentChromatogram => "tic",
+        ChromatogramType::BasePeakChromatogram => "bpc",
+        ChromatogramType::SelectedIonCurrentChromatogram => "sic",
+        ChromatogramType::SelectedIonMonitoringChromatogram => "sim",
+        ChromatogramType::SelectedReactionMonitoringChromatogram => "srm",
+        ChromatogramType::AbsorptionChromatogram => "absorption",
+        ChromatogramType::EmissionChromatogram => "emission",
+        ChromatogramType::FlowRateChromatogram => "flow-rate",
+        ChromatogramType::PressureChromatogram => "pressure",
+        // Including `Unknown` and any chromatogram types added by newer versions of mzdata
+        _ => "chromatogram",
+    }
+}
+
+/// Draw a chromatogram as a line or as a filled trace
+pub enum ChromatogramSeries<X: RenderCoordinate, Y: RenderCoordinate> {
+    Line(LineSeries<X, Y>),
+    Filled(TraceSeries<X, Y, MZ, Time, SimpleFeature<MZ, Time>>),
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> ChromatogramSeries<X, Y> {
+    pub fn new(
+        feature: SimpleFeature<MZ, Time>,
+        style: ChromatogramStyle,
+        description: SeriesDescription,
+    ) -> Self {
+        match style {
+            ChromatogramStyle::Line => Self::Line(LineSeries::new(
+                feature
+                    .iter()
+                    .map(|(_, time, inten)| (X::from(time).unwrap(), Y::from(inten).unwrap()))
+                    .collect(),
+                description,
+            )),
+            ChromatogramStyle::Filled => Self::Filled(TraceSeries::new(feature, description)),
+        }
+    }
+
+    pub fn style(&self) -> ChromatogramStyle {
+        match self {
+            Self::Line(_) => ChromatogramStyle::Line,
+            Self::Filled(_) => ChromatogramStyle::Filled,
+        }
+    }
+}
+
+impl<X: RenderCoordinate, Y: RenderCoordinate> PlotSeries<X, Y> for ChromatogramSeries<X, Y> {
+    fn description(&self) -> &SeriesDescription {
+        match self {
+            Self::Line(series) => series.description(),
+            Self::Filled(series) => series.description(),
+        }
+    }
+
+    fn description_mut(&mut self) -> &mut SeriesDescription {
+        match self {
+            Self::Line(series) => series.description_mut(),
+            Self::Filled(series) => series.description_mut(),
+        }
+    }
+
+    fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
+        match self {
+            Self::Line(series) => series.to_svg(canvas),
+            Self::Filled(series) => series.to_svg(canvas),
+        }
+    }
+
+    fn slice_x(&mut self, start: X, end: X) {
+        match self {
+            Self::Line(series) => series.slice_x(start, end),
+            Self::Filled(series) => series.slice_x(start, end),
+        }
+    }
+
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        match self {
+            Self::Line(series) => series.slice_x_segments(segments),
+            Self::Filled(series) => series.slice_x_segments(segments),
+        }
+    }
+
+    fn slice_y(&mut self, start: Y, end: Y) {
+        match self {
+            Self::Line(series) => series.slice_y(start, end),
+            Self::Filled(series) => series.slice_y(start, end),
+        }
+    }
+}
+
 
 /// Draw a scatter plot
 pub struct ScatterSeries<
@@ -873,11 +1504,13 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, R: Into<svg::node::Value> + Clone
     fn to_svg(&self, canvas: &Canvas<X, Y>) -> Group {
         self.points
             .iter()
+            .filter(|(x, ..)| canvas.x_scale().segment_of(*x).is_some())
             .fold(Group::new(), |group, (x, y, r)| {
+                let (cx, cy) = canvas.transform(*x, *y);
                 group.add(
                     Circle::new()
-                        .set("cx", canvas.x_axis.scale.transform(*x).to_f64().unwrap())
-                        .set("cy", canvas.y_axis.scale.transform(*y).to_f64().unwrap())
+                        .set("cx", cx)
+                        .set("cy", cy)
                         .set("r", r.clone()),
                 )
             })
@@ -894,6 +1527,10 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, R: Into<svg::node::Value> + Clone
             .collect();
     }
 
+    fn slice_x_segments(&mut self, segments: &[CoordinateRange<X>]) {
+        self.points.retain(|(x, ..)| within_segments(segments, *x));
+    }
+
     fn slice_y(&mut self, start: Y, end: Y) {
         self.points = std::mem::take(&mut self.points)
             .into_iter()
@@ -908,7 +1545,7 @@ impl<X: RenderCoordinate, Y: RenderCoordinate, R: Into<svg::node::Value> + Clone
     pub fn new(points: Vec<(X, Y, R)>, description: SeriesDescription) -> Self {
         Self {
             points,
-            description,
+            description: description.with_swatch(LegendSwatch::Marker),
         }
     }
 }
@@ -1014,4 +1651,82 @@ mod test {
         let doc = canvas.to_svg(&props, &props2);
         eprintln!("{}", doc.to_string())
     }
+
+    #[test]
+    fn test_broken_axis_series() {
+        let mut canvas: Canvas<f64, f32> = Canvas::new(412, 200);
+        canvas.update_scales(
+            CoordinateRange::new(100.0, 1000.0),
+            CoordinateRange::new(100.0, 0.0),
+        );
+        let segments = vec![
+            CoordinateRange::new(100.0, 300.0),
+            CoordinateRange::new(800.0, 1000.0),
+        ];
+        canvas.set_x_segments(segments.clone());
+
+        let mut series = LineSeries::new(
+            vec![
+                (150.0, 10.0),
+                (250.0, 20.0),
+                (500.0, 50.0),
+                (850.0, 30.0),
+                (900.0, 5.0),
+            ],
+            "test".into(),
+        );
+        let group = series.to_svg(&canvas).to_string();
+        assert_eq!(group.matches("<polyline").count(), 2);
+
+        series.slice_x_segments(&segments);
+        assert_eq!(series.points.len(), 4);
+
+        let series = ContinuousSeries::new(series.points.clone(), "test".into());
+        let group = series.to_svg(&canvas).to_string();
+        // One sub-path per segment
+        assert_eq!(group.matches('M').count(), 2);
+    }
+
+    #[test]
+    fn test_isotopic_envelope_series() {
+        let peaks = vec![DeconvolutedPeak::new(1000.0, 1000.0, 2, 0)];
+        let experimental: MZPeakSetType<CentroidPeak> = MZPeakSetType::new(vec![
+            CentroidPeak::new(501.0073, 550.0, 0),
+            CentroidPeak::new(501.5089, 320.0, 1),
+            CentroidPeak::new(502.0106, 100.0, 2),
+        ]);
+        let series: IsotopicEnvelopeSeries<f64, f32> = IsotopicEnvelopeSeries::from_deconvoluted(
+            peaks.into_iter(),
+            &IsotopeModel::Averagine,
+            "envelope".into(),
+        )
+        .with_fit_scores(&experimental, Tolerance::PPM(10.0));
+        let envelope = &series.envelopes[0];
+        let total: f32 = envelope.peaks.iter().map(|(_, y)| *y).sum();
+        assert!(total > 950.0 && total <= 1000.0);
+        assert!(envelope.peaks[0].1 > envelope.peaks[1].1);
+        assert!(envelope.score.unwrap() > 0.95);
+
+        let mut canvas: Canvas<f64, f32> = Canvas::new(400, 200);
+        canvas.update_scales(
+            CoordinateRange::new(500.0, 505.0),
+            CoordinateRange::new(1000.0, 0.0),
+        );
+        let text = series.to_svg(&canvas).to_string();
+        assert_eq!(text.matches("<circle").count(), envelope.peaks.len());
+        assert!(text.contains("envelope-score"));
+
+        let text = series
+            .clone()
+            .style(EnvelopeStyle::DashedSticks)
+            .to_svg(&canvas)
+            .to_string();
+        assert!(text.contains("stroke-dasharray"));
+
+        // An intensity which can't be ranked doesn't stop the score from being placed
+        let mut series = series;
+        series.envelopes[0].peaks[1].1 = f32::NAN;
+        let text = series.to_svg(&canvas).to_string();
+        assert!(text.contains("envelope-score"));
+    }
 }
@@ -0,0 +1,254 @@
+//! Extracted ion chromatograms built by summing the signal near target m/z values in each
+//! spectrum of a run.
+use mzdata::prelude::*;
+use mzdata::spectrum::RefPeakDataLevel;
+use mzpeaks::feature::SimpleFeature;
+use mzpeaks::{CentroidLike, DeconvolutedCentroidLike, Time, Tolerance, MZ};
+
+use crate::v2::{AsSeries, LineSeries, SeriesDescription};
+use crate::CoordinateRange;
+
+/// An m/z to extract a chromatogram for
+#[derive(Debug, Clone, PartialEq)]
+pub struct XicTarget {
+    pub mz: f64,
+    /// A name to show in place of the m/z
+    pub label: Option<String>,
+}
+
+impl XicTarget {
+    pub fn new(mz: f64, label: Option<String>) -> Self {
+        Self { mz, label }
+    }
+}
+
+impl From<f64> for XicTarget {
+    fn from(mz: f64) -> Self {
+        Self::new(mz, None)
+    }
+}
+
+impl From<(f64, &str)> for XicTarget {
+    fn from((mz, label): (f64, &str)) -> Self {
+        Self::new(mz, Some(label.to_string()))
+    }
+}
+
+/// The summed intensity within [`Xic::tolerance`] of [`Xic::target`] in each spectrum
+#[derive(Debug, Clone)]
+pub struct Xic {
+    pub target: XicTarget,
+    pub tolerance: Tolerance,
+    /// The name of the run the chromatogram was extracted from
+    pub run: Option<String>,
+    /// The intensity-weighted m/z, time and summed intensity of each spectrum
+    pub feature: SimpleFeature<MZ, Time>,
+}
+
+impl Xic {
+    pub fn new(target: XicTarget, tolerance: Tolerance, run: Option<String>) -> Self {
+        Self {
+            target,
+            tolerance,
+            run,
+            feature: SimpleFeature::empty(0.0),
+        }
+    }
+
+    /// The name to show for this chromatogram, including the run it came from if known
+    pub fn label(&self) -> String {
+        let target = match self.target.label.as_ref() {
+            Some(label) => label.clone(),
+            None => format!("{:.4}", self.target.mz),
+        };
+        match self.run.as_ref() {
+            Some(run) => format!("{run}: {target}"),
+            None => target,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.feature.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.feature.is_empty()
+    }
+
+    /// The time and intensity of each point
+    pub fn points(&self) -> impl Iterator<Item = (f64, f32)> + '_ {
+        self.feature.iter().map(|(_, time, inten)| (time, inten))
+    }
+}
+
+impl AsSeries<f64, f32> for Xic {
+    type Series = LineSeries<f64, f32>;
+
+    fn as_series(&self) -> Self::Series {
+        LineSeries::new(self.points().collect(), SeriesDescription::from("xic"))
+    }
+}
+
+/// Extract chromatograms for a set of target m/z values from the spectra of a run
+#[derive(Debug, Clone)]
+pub struct XicExtractor {
+    pub targets: Vec<XicTarget>,
+    pub tolerance: Tolerance,
+    /// Only spectra whose scan start time falls within this range are used
+    pub time_range: Option<CoordinateRange<f64>>,
+    pub ms_level: u8,
+    /// The name of the run, used to label the chromatograms
+    pub run: Option<String>,
+}
+
+impl XicExtractor {
+    pub fn new<T: Into<XicTarget>>(
+        targets: impl IntoIterator<Item = T>,
+        tolerance: Tolerance,
+    ) -> Self {
+        Self {
+            targets: targets.into_iter().map(Into::into).collect(),
+            tolerance,
+            time_range: None,
+            ms_level: 1,
+            run: None,
+        }
+    }
+
+    pub fn time_range(mut self, start: f64, end: f64) -> Self {
+        self.time_range = Some(CoordinateRange::new(start, end));
+        self
+    }
+
+    pub fn ms_level(mut self, ms_level: u8) -> Self {
+        self.ms_level = ms_level;
+        self
+    }
+
+    pub fn run(mut self, run: impl ToString) -> Self {
+        self.run = Some(run.to_string());
+        self
+    }
+
+    /// Extract one chromatogram per target from `spectra`, which are assumed to be in
+    /// acquisition order
+    pub fn extract<C: CentroidLike, D: DeconvolutedCentroidLike, S: SpectrumLike<C, D>>(
+        &self,
+        spectra: impl IntoIterator<Item = S>,
+    ) -> Vec<Xic> {
+        let mut xics: Vec<Xic> = self
+            .targets
+            .iter()
+            .map(|target| Xic::new(target.clone(), self.tolerance, self.run.clone()))
+            .collect();
+        let mut totals = vec![(0.0f64, 0.0f32); xics.len()];
+        for spectrum in spectra {
+            if spectrum.ms_level() != self.ms_level {
+                continue;
+            }
+            let time = spectrum.start_time();
+            if let Some(range) = self.time_range.as_ref() {
+                if time < range.min() {
+                    continue;
+                }
+                if time > range.max() {
+                    break;
+                }
+            }
+            totals.fill((0.0, 0.0));
+            let peaks = spectrum.peaks();
+            if let RefPeakDataLevel::Centroid(centroids) = peaks {
+                for (xic, (weighted, total)) in xics.iter().zip(totals.iter_mut()) {
+                    for peak in centroids.all_peaks_for(xic.target.mz, self.tolerance) {
+                        *weighted += peak.mz() * peak.intensity() as f64;
+                        *total += peak.intensity();
+                    }
+                }
+            } else {
+                // Profile signal and deconvoluted peaks aren't searchable by m/z
+                for point in peaks.iter() {
+                    for (xic, (weighted, total)) in xics.iter().zip(totals.iter_mut()) {
+                        if self.tolerance.test(point.mz, xic.target.mz) {
+                            *weighted += point.mz * point.intensity as f64;
+                            *total += point.intensity;
+                        }
+                    }
+                }
+            }
+            for (xic, (weighted, total)) in xics.iter_mut().zip(totals.iter()) {
+                let mz = if *total > 0.0 {
+                    weighted / *total as f64
+                } else {
+                    xic.target.mz
+                };
+                xic.feature.push_raw(mz, time, *total);
+            }
+        }
+        xics
+    }
+
+    /// Extract one chromatogram per target from every spectrum in `reader`, starting from
+    /// the beginning of the run
+    pub fn extract_from<
+        C: CentroidLike + Default,
+        D: DeconvolutedCentroidLike + Default,
+        S: SpectrumLike<C, D>,
+        R: SpectrumSource<C, D, S>,
+    >(
+        &self,
+        reader: &mut R,
+    ) -> Vec<Xic> {
+        reader.reset();
+        self.extract(reader.by_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mzdata::spectrum::MultiLayerSpectrum;
+    use mzpeaks::{CentroidPeak, MZPeakSetType};
+
+    fn spectrum(time: f64, peaks: &[(f64, f32)]) -> MultiLayerSpectrum {
+        let mut spectrum = MultiLayerSpectrum::default();
+        spectrum.description_mut().ms_level = 1;
+        spectrum
+            .description_mut()
+            .acquisition
+            .first_scan_mut()
+            .unwrap()
+            .start_time = time;
+        spectrum.peaks = Some(MZPeakSetType::new(
+            peaks
+                .iter()
+                .enumerate()
+                .map(|(i, (mz, inten))| CentroidPeak::new(*mz, *inten, i as u32))
+                .collect(),
+        ));
+        spectrum
+    }
+
+    #[test]
+    fn test_extract_xics() {
+        let spectra = vec![
+            spectrum(1.0, &[(500.0, 10.0), (600.0, 5.0)]),
+            spectrum(2.0, &[(500.002, 30.0), (500.1, 100.0), (600.0, 50.0)]),
+            spectrum(3.0, &[(600.0, 20.0)]),
+            spectrum(4.0, &[(500.0, 10.0)]),
+        ];
+        let xics = XicExtractor::new([(500.0, "A"), (600.0, "B")], Tolerance::PPM(10.0))
+            .time_range(1.5, 3.5)
+            .run("run 1")
+            .extract(spectra);
+        assert_eq!(xics.len(), 2);
+        let a: Vec<_> = xics[0].points().collect();
+        assert_eq!(a, [(2.0, 30.0), (3.0, 0.0)]);
+        let b: Vec<_> = xics[1].points().collect();
+        assert_eq!(b, [(2.0, 50.0), (3.0, 20.0)]);
+        assert_eq!(xics[0].label(), "run 1: A");
+        assert_eq!(
+            Xic::new(450.25.into(), Tolerance::Da(0.01), None).label(),
+            "450.2500"
+        );
+    }
+}